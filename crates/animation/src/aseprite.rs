@@ -0,0 +1,364 @@
+use crate::{loader::prefixed, AnimationDefinition, AnimationDirection, AnimationSet};
+use anyhow::anyhow;
+use asefile::AsepriteFile;
+use bevy::{
+  asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+  prelude::*,
+  reflect::TypeUuid,
+  render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+  sprite::Rect,
+  utils::BoxedFuture,
+};
+use serde::{
+  de::{value::StrDeserializer, DeserializeOwned, IntoDeserializer},
+  Deserialize,
+};
+use std::{fmt::Debug, hash::Hash, marker::PhantomData, path::Path};
+
+pub const ATLAS_LABEL: &str = "atlas";
+pub const TEXTURE_LABEL: &str = "texture";
+
+// A tag as read from either format, frame indices are inclusive.
+struct SheetTag {
+  name: String,
+  from: usize,
+  to: usize,
+  repeat: bool,
+  direction: AnimationDirection,
+}
+
+// Tags that don't name a key of `T` are skipped so the art can carry tags the game doesn't use.
+fn animation_set<T>(tags: Vec<SheetTag>, durations: &[u32]) -> AnimationSet<T>
+where
+  T: Eq + Hash + DeserializeOwned,
+{
+  tags
+    .into_iter()
+    .filter_map(|tag| {
+      let de: StrDeserializer<serde::de::value::Error> = tag.name.as_str().into_deserializer();
+      match T::deserialize(de) {
+        Ok(key) => Some((key, tag)),
+        Err(_) => {
+          warn!("ignoring aseprite tag `{}`", tag.name);
+          None
+        }
+      }
+    })
+    .map(|(key, tag)| {
+      let total: u32 = durations[tag.from..=tag.to].iter().sum();
+      let frames = (tag.to - tag.from + 1) as f32;
+      (
+        key,
+        AnimationDefinition {
+          start: tag.from,
+          end: tag.to,
+          fps: frames * 1000. / total.max(1) as f32,
+          repeat: tag.repeat,
+          direction: tag.direction,
+          frame_durations: Some(
+            durations[tag.from..=tag.to]
+              .iter()
+              .map(|ms| *ms as f32 / 1000.)
+              .collect(),
+          ),
+          ..Default::default()
+        },
+      )
+    })
+    .collect()
+}
+
+/// Loads `.aseprite` files into an [`AnimationSet`] built from the file's tags. The frames are
+/// packed into a single row and available as labeled `atlas` and `texture` assets.
+pub struct AsepriteLoader<T> {
+  extensions: Vec<&'static str>,
+  phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for AsepriteLoader<T> {
+  fn default() -> Self {
+    Self {
+      extensions: vec!["aseprite", "ase"],
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<T> AsepriteLoader<T> {
+  /// Only loads `<name>.<prefix>.aseprite` and `<name>.<prefix>.ase` files.
+  pub fn with_extension_prefix(mut self, prefix: &str) -> Self {
+    self.extensions = prefixed(prefix, &self.extensions);
+    self
+  }
+}
+
+impl<T> AssetLoader for AsepriteLoader<T>
+where
+  T: Send + Sync + Eq + Hash + Clone + Debug + TypeUuid + DeserializeOwned + 'static,
+{
+  fn load<'a>(
+    &'a self,
+    bytes: &'a [u8],
+    load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+    Box::pin(async move {
+      let ase = AsepriteFile::read(bytes)?;
+      let (width, height) = (ase.width(), ase.height());
+      let frame_count = ase.num_frames() as usize;
+
+      // pack frames left to right
+      let stride = width * frame_count * 4;
+      let mut data = vec![0; stride * height];
+      let mut durations = Vec::with_capacity(frame_count);
+      for i in 0..frame_count {
+        let frame = ase.frame(i as u32);
+        durations.push(frame.duration());
+        let pixels = frame.image().into_raw();
+        for row in 0..height {
+          let src = row * width * 4;
+          let dst = row * stride + i * width * 4;
+          data[dst..dst + width * 4].copy_from_slice(&pixels[src..src + width * 4]);
+        }
+      }
+
+      let image = Image::new(
+        Extent3d {
+          width: (width * frame_count) as u32,
+          height: height as u32,
+          depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+      );
+      let texture = load_context.set_labeled_asset(TEXTURE_LABEL, LoadedAsset::new(image));
+      let atlas = TextureAtlas::from_grid(
+        texture,
+        Vec2::new(width as f32, height as f32),
+        frame_count,
+        1,
+      );
+      load_context.set_labeled_asset(ATLAS_LABEL, LoadedAsset::new(atlas));
+
+      let tags = (0..ase.num_tags())
+        .map(|i| {
+          let tag = ase.tag(i);
+          SheetTag {
+            name: tag.name().to_string(),
+            from: tag.from_frame() as usize,
+            to: tag.to_frame() as usize,
+            repeat: true,
+            direction: match tag.animation_direction() {
+              asefile::AnimationDirection::Forward => AnimationDirection::Forward,
+              asefile::AnimationDirection::Reverse => AnimationDirection::Reverse,
+              asefile::AnimationDirection::PingPong => AnimationDirection::PingPong,
+            },
+          }
+        })
+        .collect();
+      let set = animation_set::<T>(tags, &durations);
+      set.validate(Some(durations.len()))?;
+      load_context.set_default_asset(LoadedAsset::new(set));
+      Ok(())
+    })
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &self.extensions
+  }
+}
+
+#[derive(Deserialize)]
+struct JsonRect {
+  x: f32,
+  y: f32,
+  w: f32,
+  h: f32,
+}
+
+#[derive(Deserialize)]
+struct JsonFrame {
+  frame: JsonRect,
+  duration: u32,
+}
+
+// Aseprite can export frames either as an array or as a map keyed by file name.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonFrames {
+  Array(Vec<JsonFrame>),
+  Hash(serde_json::Map<String, serde_json::Value>),
+}
+
+#[derive(Deserialize)]
+struct JsonSize {
+  w: f32,
+  h: f32,
+}
+
+#[derive(Deserialize)]
+struct JsonTag {
+  name: String,
+  from: usize,
+  to: usize,
+  // only present when the tag is set to play a limited number of times
+  repeat: Option<String>,
+  #[serde(default)]
+  direction: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonMeta {
+  image: String,
+  size: JsonSize,
+  #[serde(default)]
+  frame_tags: Vec<JsonTag>,
+}
+
+#[derive(Deserialize)]
+struct JsonSheet {
+  frames: JsonFrames,
+  meta: JsonMeta,
+}
+
+/// A frame of an exported sprite sheet, in playback order.
+#[derive(Clone, Debug)]
+pub struct SheetFrame {
+  pub rect: Rect,
+  // in milliseconds
+  pub duration: u32,
+}
+
+// The trailing number of a hash key such as `player 12.aseprite`.
+fn frame_number(name: &str) -> Option<usize> {
+  let stem = Path::new(name).file_stem()?.to_str()?;
+  let number = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+  stem[number.len()..].parse().ok()
+}
+
+fn sheet_frames(frames: JsonFrames) -> Result<Vec<SheetFrame>, anyhow::Error> {
+  let frames: Vec<JsonFrame> = match frames {
+    JsonFrames::Array(frames) => frames,
+    JsonFrames::Hash(frames) => {
+      // the map iterates in key order, which puts `player 10` before `player 2`
+      let mut frames: Vec<_> = frames.into_iter().collect();
+      frames.sort_by_key(|(name, _)| frame_number(name));
+      frames
+        .into_iter()
+        .map(|(_, frame)| serde_json::from_value(frame))
+        .collect::<Result<_, _>>()?
+    }
+  };
+  Ok(
+    frames
+      .into_iter()
+      .map(|frame| {
+        let JsonRect { x, y, w, h } = frame.frame;
+        SheetFrame {
+          rect: Rect {
+            min: Vec2::new(x, y),
+            max: Vec2::new(x + w, y + h),
+          },
+          duration: frame.duration,
+        }
+      })
+      .collect(),
+  )
+}
+
+/// Reads the frames of an Aseprite JSON export, ordered by frame number when exported as a hash.
+pub fn read_sheet_frames(bytes: &[u8]) -> Result<Vec<SheetFrame>, anyhow::Error> {
+  let sheet: JsonSheet = serde_json::from_slice(bytes)?;
+  sheet_frames(sheet.frames)
+}
+
+/// Loads the JSON data written by Aseprite's sprite sheet export. The exported image is loaded
+/// relative to the JSON file.
+pub struct AsepriteJsonLoader<T> {
+  extensions: Vec<&'static str>,
+  phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for AsepriteJsonLoader<T> {
+  fn default() -> Self {
+    Self {
+      extensions: vec!["aseprite.json"],
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<T> AsepriteJsonLoader<T> {
+  /// Only loads `<name>.<prefix>.aseprite.json` files.
+  pub fn with_extension_prefix(mut self, prefix: &str) -> Self {
+    self.extensions = prefixed(prefix, &self.extensions);
+    self
+  }
+}
+
+impl<T> AssetLoader for AsepriteJsonLoader<T>
+where
+  T: Send + Sync + Eq + Hash + Clone + Debug + TypeUuid + DeserializeOwned + 'static,
+{
+  fn load<'a>(
+    &'a self,
+    bytes: &'a [u8],
+    load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+    Box::pin(async move {
+      let sheet: JsonSheet = serde_json::from_slice(bytes)?;
+      let frames = sheet_frames(sheet.frames)?;
+      if let Some(tag) = sheet.meta.frame_tags.iter().find(|t| t.to >= frames.len()) {
+        return Err(anyhow!("tag `{}` ends past the last frame", tag.name));
+      }
+      if let Some(tag) = sheet.meta.frame_tags.iter().find(|t| t.from > t.to) {
+        return Err(anyhow!("tag `{}` starts after it ends", tag.name));
+      }
+
+      let image_path = load_context
+        .path()
+        .parent()
+        .map(|dir| dir.join(&sheet.meta.image))
+        .unwrap_or_else(|| sheet.meta.image.clone().into());
+      let image_path = AssetPath::new(image_path, None);
+      let texture = load_context.get_handle(image_path.clone());
+
+      let mut atlas =
+        TextureAtlas::new_empty(texture, Vec2::new(sheet.meta.size.w, sheet.meta.size.h));
+      for frame in frames.iter() {
+        atlas.add_texture(frame.rect);
+      }
+      load_context.set_labeled_asset(
+        ATLAS_LABEL,
+        LoadedAsset::new(atlas).with_dependency(image_path),
+      );
+
+      let durations: Vec<_> = frames.iter().map(|f| f.duration).collect();
+      let tags = sheet
+        .meta
+        .frame_tags
+        .into_iter()
+        .map(|tag| SheetTag {
+          repeat: tag.repeat.is_none(),
+          direction: match tag.direction.as_str() {
+            "reverse" => AnimationDirection::Reverse,
+            "pingpong" | "pingpong_reverse" => AnimationDirection::PingPong,
+            _ => AnimationDirection::Forward,
+          },
+          name: tag.name,
+          from: tag.from,
+          to: tag.to,
+        })
+        .collect();
+      let set = animation_set::<T>(tags, &durations);
+      set.validate(Some(durations.len()))?;
+      load_context.set_default_asset(LoadedAsset::new(set));
+      Ok(())
+    })
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &self.extensions
+  }
+}
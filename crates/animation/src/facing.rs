@@ -0,0 +1,91 @@
+use crate::{AnimationDefinition, AnimationSet, RequestedAnimation};
+use bevy::{prelude::*, reflect::TypeUuid, sprite::Anchor};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::hash::Hash;
+
+/// The direction an entity looks in. Art is assumed to face right, so facing left mirrors the
+/// sprite unless the animation has a `Left` variant.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize)]
+pub enum Facing {
+  Left,
+  #[default]
+  Right,
+  Up,
+  Down,
+}
+
+impl AnimationDefinition {
+  /// The definition to play when facing `facing`, and whether it has to be mirrored.
+  pub fn for_facing(&self, facing: Facing) -> (&AnimationDefinition, bool) {
+    let variant = self.variant_for(facing);
+    let mirrored = facing == Facing::Left && variant != Some(Facing::Left);
+    (self.variant(variant), mirrored)
+  }
+
+  /// Which of the variants `for_facing` plays, `None` for the definition itself.
+  pub fn variant_for(&self, facing: Facing) -> Option<Facing> {
+    if self.variants.contains_key(&facing) {
+      return Some(facing);
+    }
+    match facing {
+      Facing::Left if self.variants.contains_key(&Facing::Right) => Some(Facing::Right),
+      _ => None,
+    }
+  }
+
+  pub fn variant(&self, variant: Option<Facing>) -> &AnimationDefinition {
+    variant
+      .and_then(|facing| self.variants.get(&facing))
+      .unwrap_or(self)
+  }
+}
+
+impl<T> AnimationSet<T>
+where
+  T: Eq + Hash,
+{
+  pub fn get_facing(
+    &self,
+    key: &T,
+    facing: Option<&Facing>,
+  ) -> Option<(&AnimationDefinition, bool)> {
+    let def = self.get(key)?;
+    Some(match facing {
+      Some(facing) => def.for_facing(*facing),
+      None => (def, false),
+    })
+  }
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn face_sprites<T>(
+  sets: Res<Assets<AnimationSet<T>>>,
+  mut qry: Query<(
+    &Facing,
+    &RequestedAnimation<T>,
+    &Handle<AnimationSet<T>>,
+    &mut TextureAtlasSprite,
+  )>,
+) where
+  T: Send + Sync + Eq + Hash + Clone + TypeUuid + DeserializeOwned + 'static,
+{
+  for (facing, req, handle, mut sprite) in qry.iter_mut() {
+    let flip = match sets.get(handle).and_then(|set| set.get_facing(&req.play, Some(facing))) {
+      Some((_, flip)) => flip,
+      None => continue,
+    };
+
+    set_flip(&mut sprite, flip);
+  }
+}
+
+// Mirrors the anchor along with the sprite, only touching it when the flip actually changes.
+pub(crate) fn set_flip(sprite: &mut Mut<TextureAtlasSprite>, flip: bool) {
+  if sprite.flip_x != flip {
+    sprite.flip_x = flip;
+    let anchor = sprite.anchor.as_vec();
+    if anchor.x != 0. {
+      sprite.anchor = Anchor::Custom(Vec2::new(-anchor.x, anchor.y));
+    }
+  }
+}
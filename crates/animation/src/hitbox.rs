@@ -0,0 +1,158 @@
+use crate::{AnimationSet, Facing, RequestedAnimation};
+use bevy::{prelude::*, reflect::TypeUuid};
+use game_data::PhysicsLayer;
+use heron::{prelude::*, SensorShape};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::hash::Hash;
+
+/// A rectangle in pixels relative to the sprite's center, y pointing up.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct FrameRect {
+  pub offset: (f32, f32),
+  pub size: (f32, f32),
+}
+
+/// Collision rectangles for a single frame. Hitboxes send damage, hurtboxes receive it.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct FrameBoxes {
+  #[serde(default)]
+  pub hitboxes: Vec<FrameRect>,
+  #[serde(default)]
+  pub hurtboxes: Vec<FrameRect>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BoxKind {
+  Hit,
+  Hurt,
+}
+
+/// Marks the sensor children kept in sync with the parent's current frame.
+#[derive(Component)]
+pub struct FrameCollider {
+  pub kind: BoxKind,
+  slot: usize,
+}
+
+/// Collision layers used for the frame colliders of an entity, entities without it don't get
+/// any. The entity needs a `RigidBody` for the sensors to attach to.
+#[derive(Component, Clone, Copy)]
+pub struct HitboxLayers {
+  pub hit: CollisionLayers,
+  pub hurt: CollisionLayers,
+}
+
+impl HitboxLayers {
+  pub fn player() -> Self {
+    Self {
+      hit: CollisionLayers::none()
+        .with_group(PhysicsLayer::PlayerDamageSend)
+        .with_mask(PhysicsLayer::EnemyDamageReceive),
+      hurt: CollisionLayers::none()
+        .with_group(PhysicsLayer::PlayerDamageReceive)
+        .with_mask(PhysicsLayer::EnemyDamageSend),
+    }
+  }
+
+  pub fn enemy() -> Self {
+    Self {
+      hit: CollisionLayers::none()
+        .with_group(PhysicsLayer::EnemyDamageSend)
+        .with_mask(PhysicsLayer::PlayerDamageReceive),
+      hurt: CollisionLayers::none()
+        .with_group(PhysicsLayer::EnemyDamageReceive)
+        .with_mask(PhysicsLayer::PlayerDamageSend),
+    }
+  }
+
+  fn for_kind(&self, kind: BoxKind) -> CollisionLayers {
+    match kind {
+      BoxKind::Hit => self.hit,
+      BoxKind::Hurt => self.hurt,
+    }
+  }
+}
+
+// boxes are authored for the unflipped art
+fn shape(rect: &FrameRect, flip_x: bool) -> (Transform, CollisionShape) {
+  let x = if flip_x { -rect.offset.0 } else { rect.offset.0 };
+  (
+    Transform::from_xyz(x, rect.offset.1, 0.),
+    CollisionShape::Cuboid {
+      half_extends: Vec3::new(rect.size.0 / 2., rect.size.1 / 2., 0.),
+      border_radius: None,
+    },
+  )
+}
+
+// Existing colliders are reused by kind and slot so only their shape changes between frames.
+#[allow(clippy::type_complexity)]
+pub(crate) fn sync_frame_colliders<T>(
+  mut commands: Commands,
+  sets: Res<Assets<AnimationSet<T>>>,
+  qry: Query<
+    (
+      Entity,
+      &RequestedAnimation<T>,
+      &Handle<AnimationSet<T>>,
+      &TextureAtlasSprite,
+      Option<&Facing>,
+      &HitboxLayers,
+      Option<&Children>,
+    ),
+    Changed<TextureAtlasSprite>,
+  >,
+  mut colliders: Query<(&FrameCollider, &mut Transform, &mut CollisionShape)>,
+) where
+  T: Send + Sync + Eq + Hash + Clone + TypeUuid + DeserializeOwned + 'static,
+{
+  for (entity, req, handle, sprite, facing, layers, children) in qry.iter() {
+    let boxes = sets
+      .get(handle)
+      .and_then(|set| set.get_facing(&req.play, facing))
+      .and_then(|(def, _)| def.boxes.get(&sprite.index));
+    let wanted: Vec<_> = boxes
+      .map(|b| {
+        let hit = b.hitboxes.iter().enumerate().map(|(i, r)| (BoxKind::Hit, i, r));
+        let hurt = b.hurtboxes.iter().enumerate().map(|(i, r)| (BoxKind::Hurt, i, r));
+        hit.chain(hurt).collect()
+      })
+      .unwrap_or_default();
+
+    let mut reused = Vec::new();
+    for &child in children.iter().flat_map(|c| c.iter()) {
+      if let Ok((collider, mut transform, mut collision_shape)) = colliders.get_mut(child) {
+        match wanted
+          .iter()
+          .find(|(kind, slot, _)| *kind == collider.kind && *slot == collider.slot)
+        {
+          Some((kind, slot, rect)) => {
+            let (new_transform, new_shape) = shape(rect, sprite.flip_x);
+            *transform = new_transform;
+            *collision_shape = new_shape;
+            reused.push((*kind, *slot));
+          }
+          None => commands.entity(child).despawn_recursive(),
+        }
+      }
+    }
+
+    commands.entity(entity).with_children(|parent| {
+      for (kind, slot, rect) in wanted.iter() {
+        if reused.contains(&(*kind, *slot)) {
+          continue;
+        }
+        let (transform, collision_shape) = shape(rect, sprite.flip_x);
+        parent
+          .spawn_bundle(TransformBundle::from_transform(transform))
+          .insert(collision_shape)
+          .insert(SensorShape)
+          .insert(layers.for_kind(*kind))
+          .insert(FrameCollider {
+            kind: *kind,
+            slot: *slot,
+          });
+      }
+    });
+  }
+}
@@ -0,0 +1,66 @@
+use crate::{facing::set_flip, AnimationSet, Facing, RequestedAnimation};
+use bevy::{prelude::*, reflect::TypeUuid};
+use serde::de::DeserializeOwned;
+use std::hash::Hash;
+
+/// A child sprite (weapon, hat, armour...) drawn from its own atlas in sync with its parent's
+/// animation. With its own `Handle<AnimationSet<T>>` the parent's frame is mapped onto the
+/// layer's definition for the same key, otherwise the atlas is expected to share the parent's
+/// frame layout and the index is copied as is.
+#[derive(Component, Default)]
+pub struct AnimationLayer;
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn sync_layers<T>(
+  sets: Res<Assets<AnimationSet<T>>>,
+  parents: Query<
+    (
+      &RequestedAnimation<T>,
+      &Handle<AnimationSet<T>>,
+      Option<&Facing>,
+      &TextureAtlasSprite,
+    ),
+    Without<AnimationLayer>,
+  >,
+  mut layers: Query<
+    (
+      &Parent,
+      Option<&Handle<AnimationSet<T>>>,
+      &mut TextureAtlasSprite,
+    ),
+    With<AnimationLayer>,
+  >,
+) where
+  T: Send + Sync + Eq + Hash + Clone + TypeUuid + DeserializeOwned + 'static,
+{
+  for (parent, layer_set, mut sprite) in layers.iter_mut() {
+    let (req, handle, facing, parent_sprite) = match parents.get(parent.get()) {
+      Ok(parent) => parent,
+      Err(_) => continue,
+    };
+
+    let index = match layer_set {
+      Some(layer_set) => {
+        let parent_def = sets
+          .get(handle)
+          .and_then(|set| set.get_facing(&req.play, facing));
+        let layer_def = sets
+          .get(layer_set)
+          .and_then(|set| set.get_facing(&req.play, facing));
+        match (parent_def, layer_def) {
+          (Some((parent_def, _)), Some((layer_def, _))) => {
+            let offset = parent_sprite.index.saturating_sub(parent_def.start);
+            (layer_def.start + offset).min(layer_def.end)
+          }
+          _ => continue,
+        }
+      }
+      None => parent_sprite.index,
+    };
+
+    if sprite.index != index {
+      sprite.index = index;
+    }
+    set_flip(&mut sprite, parent_sprite.flip_x);
+  }
+}
@@ -1,30 +1,193 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, reflect::TypeUuid, utils::Uuid};
+use game_utils::GameRng;
 use rand::distributions::{Distribution, Uniform};
-use std::{collections::HashMap, hash::Hash, marker::PhantomData};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+  collections::{HashMap, HashSet},
+  fmt::Debug,
+  hash::Hash,
+  marker::PhantomData,
+  time::Duration,
+};
 
-#[derive(Clone)]
+mod aseprite;
+mod facing;
+mod hitbox;
+mod layer;
+mod loader;
+mod pivot;
+mod queue;
+mod state_machine;
+mod target;
+mod validation;
+pub use aseprite::*;
+pub use facing::*;
+pub use hitbox::*;
+pub use layer::*;
+pub use loader::*;
+pub use pivot::*;
+pub use queue::*;
+pub use state_machine::*;
+pub use target::*;
+pub use validation::*;
+
+#[derive(SystemLabel, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AnimationSystem {
+  StateMachine,
+  Queue,
+  Init,
+  Facing,
+  Animate,
+  Layers,
+  Colliders,
+}
+
+#[derive(Clone, Default, Deserialize)]
 pub struct AnimationDefinition {
   pub start: usize,
   pub end: usize,
   pub fps: f32,
   pub repeat: bool,
+  #[serde(default)]
   pub repeat_from: Option<usize>,
+  #[serde(default)]
   pub random_start: bool,
+  #[serde(default)]
+  pub events: Vec<FrameEvent>,
+  // seconds each frame from `start` to `end` is shown, frames without an entry use `fps`
+  #[serde(default)]
+  pub frame_durations: Option<Vec<f32>>,
+  #[serde(default)]
+  pub direction: AnimationDirection,
+  // collision rectangles keyed by atlas index
+  #[serde(default)]
+  pub boxes: HashMap<usize, FrameBoxes>,
+  // replaces this definition when facing a direction, see `for_facing`
+  #[serde(default)]
+  pub variants: HashMap<Facing, AnimationDefinition>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum AnimationDirection {
+  #[default]
+  Forward,
+  Reverse,
+  // plays to the end and back, a non-repeating ping-pong completes back on its first frame
+  PingPong,
 }
+
 impl AnimationDefinition {
   pub fn duration_seconds(&self) -> f32 {
-    (self.end - self.start + 1) as f32 / self.fps
+    (self.start..=self.end)
+      .map(|frame| self.frame_duration(frame))
+      .sum()
+  }
+
+  pub fn frame_duration(&self, frame: usize) -> f32 {
+    self
+      .frame_durations
+      .as_ref()
+      .and_then(|durations| durations.get(frame.checked_sub(self.start)?))
+      .copied()
+      .unwrap_or(1. / self.fps)
+  }
+
+  /// The names of the events on `frame`, each name once even if it is listed twice.
+  pub fn events_at(&self, frame: usize) -> impl Iterator<Item = &str> {
+    let mut seen = HashSet::new();
+    self
+      .events
+      .iter()
+      .filter(move |ev| ev.frame == frame && seen.insert(ev.name.as_str()))
+      .map(|ev| ev.name.as_str())
   }
 }
 
+/// Names a frame (an atlas index, like `start` and `end`) so gameplay can react when it is shown.
+#[derive(Clone, Deserialize)]
+pub struct FrameEvent {
+  pub frame: usize,
+  pub name: String,
+}
+
+/// Sent when an animation shows a frame that has events attached, once per event name.
+pub struct AnimationFrameEvent<T> {
+  pub entity: Entity,
+  pub animation: T,
+  pub name: String,
+}
+
+/// Sent once when a non-repeating animation shows its last frame.
+pub struct AnimationFinished<T> {
+  pub entity: Entity,
+  pub animation: T,
+}
+
 #[derive(Clone)]
 pub struct AnimationSet<T> {
   animations: HashMap<T, AnimationDefinition>,
 }
 
+// Every key type gets its own asset type, so the uuid is derived from the key's.
+impl<T: TypeUuid> TypeUuid for AnimationSet<T> {
+  const TYPE_UUID: Uuid =
+    Uuid::from_u128(0x8c1e_55a4_2f3b_4d7e_9a61_0b7d_e4c2_93f1 ^ T::TYPE_UUID.as_u128());
+}
+
+impl<T> Default for AnimationSet<T> {
+  fn default() -> Self {
+    Self {
+      animations: HashMap::new(),
+    }
+  }
+}
+
+impl<T> AnimationSet<T>
+where
+  T: Eq + Hash,
+{
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert(&mut self, key: T, definition: AnimationDefinition) -> Option<AnimationDefinition> {
+    self.animations.insert(key, definition)
+  }
+
+  pub fn with(mut self, key: T, definition: AnimationDefinition) -> Self {
+    self.insert(key, definition);
+    self
+  }
+
+  pub fn get(&self, key: &T) -> Option<&AnimationDefinition> {
+    self.animations.get(key)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&T, &AnimationDefinition)> {
+    self.animations.iter()
+  }
+}
+
+impl<T> FromIterator<(T, AnimationDefinition)> for AnimationSet<T>
+where
+  T: Eq + Hash,
+{
+  fn from_iter<I: IntoIterator<Item = (T, AnimationDefinition)>>(iter: I) -> Self {
+    Self {
+      animations: iter.into_iter().collect(),
+    }
+  }
+}
+
 #[derive(Component, Clone)]
 pub struct RequestedAnimation<T> {
-  play: T,
+  pub play: T,
+}
+
+impl<T> RequestedAnimation<T> {
+  pub fn new(play: T) -> Self {
+    Self { play }
+  }
 }
 
 #[derive(Component)]
@@ -32,101 +195,340 @@ pub struct PlayingAnimation {
   pub timer: Timer,
   pub start_frame: usize,
   pub complete: bool,
+  // stepping towards `start`, flips on every ping-pong bounce
+  pub backwards: bool,
+  // the facing variant playing, see `AnimationDefinition::variant_for`
+  pub variant: Option<Facing>,
+}
+
+/// Optional per-entity playback controls, entities without it play at normal speed.
+#[derive(Component, Clone, Copy)]
+pub struct AnimationPlayback {
+  pub speed: f32,
+  pub paused: bool,
+  // plays the definition in the opposite direction
+  pub reverse: bool,
+}
+
+impl Default for AnimationPlayback {
+  fn default() -> Self {
+    Self {
+      speed: 1.,
+      paused: false,
+      reverse: false,
+    }
+  }
+}
+
+/// Scales the time of every animation, 0 freezes them all.
+pub struct AnimationTimeScale(pub f32);
+
+impl Default for AnimationTimeScale {
+  fn default() -> Self {
+    Self(1.)
+  }
 }
 
+/// Asset loaders are picked by extension and the last one registered wins, so when the plugin is
+/// added for more than one key type each needs its own extension prefix.
 pub struct AnimationPlugin<T> {
+  extension_prefix: Option<&'static str>,
   phantom: PhantomData<T>,
 }
 
+impl<T> Default for AnimationPlugin<T> {
+  fn default() -> Self {
+    Self {
+      extension_prefix: None,
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<T> AnimationPlugin<T> {
+  /// Loads animations for `T` from `<name>.<prefix>.<extension>` files only, e.g.
+  /// `hero.player.aseprite` or `idle.player.piv` for the prefix `player`.
+  pub fn with_extension_prefix(prefix: &'static str) -> Self {
+    Self {
+      extension_prefix: Some(prefix),
+      phantom: PhantomData,
+    }
+  }
+}
+
 impl<T> Plugin for AnimationPlugin<T>
 where
-  T: Send + Sync + Eq + Hash + 'static,
+  T: Send + Sync + Eq + Hash + Clone + Debug + TypeUuid + DeserializeOwned + 'static,
 {
   fn build(&self, app: &mut App) {
+    match self.extension_prefix {
+      Some(prefix) => app
+        .add_asset_loader(AsepriteLoader::<T>::default().with_extension_prefix(prefix))
+        .add_asset_loader(AsepriteJsonLoader::<T>::default().with_extension_prefix(prefix))
+        .add_asset_loader(AnimationSetLoader::<T>::default().with_extension_prefix(prefix))
+        .add_asset_loader(PivotLoader::<T>::default().with_extension_prefix(prefix)),
+      None => app
+        .init_asset_loader::<AsepriteLoader<T>>()
+        .init_asset_loader::<AsepriteJsonLoader<T>>()
+        .init_asset_loader::<AnimationSetLoader<T>>()
+        .init_asset_loader::<PivotLoader<T>>(),
+    };
     app
-      .add_system(Self::init_atlas_animation)
-      .add_system(Self::animate_sprites);
+      .init_resource::<AnimationTimeScale>()
+      .init_resource::<GameRng>()
+      .add_asset::<AnimationSet<T>>()
+      .add_event::<AnimationFrameEvent<T>>()
+      .add_event::<AnimationFinished<T>>()
+      .add_system(
+        state_machine::drive_state_machines::<T>
+          .label(AnimationSystem::StateMachine)
+          .before(AnimationSystem::Queue),
+      )
+      .add_system(
+        queue::advance_queues::<T>
+          .label(AnimationSystem::Queue)
+          .before(AnimationSystem::Init),
+      )
+      .add_system(
+        Self::init_animation::<TextureAtlasSprite>
+          .label(AnimationSystem::Init)
+          .before(AnimationSystem::Facing),
+      )
+      .add_system(
+        Self::init_animation::<ImageSequence>
+          .label(AnimationSystem::Init)
+          .before(AnimationSystem::Facing),
+      )
+      .add_system(
+        facing::face_sprites::<T>
+          .label(AnimationSystem::Facing)
+          .before(AnimationSystem::Animate),
+      )
+      .add_system(Self::animate::<TextureAtlasSprite>.label(AnimationSystem::Animate))
+      .add_system(Self::animate::<ImageSequence>.label(AnimationSystem::Animate))
+      .add_system(target::apply_image_sequences.after(AnimationSystem::Animate))
+      .add_system(
+        layer::sync_layers::<T>
+          .label(AnimationSystem::Layers)
+          .after(AnimationSystem::Animate),
+      )
+      .add_system(
+        hitbox::sync_frame_colliders::<T>
+          .label(AnimationSystem::Colliders)
+          .after(AnimationSystem::Animate),
+      );
   }
 }
 
 impl<T> AnimationPlugin<T>
 where
-  T: Send + Sync + Eq + Hash + 'static,
+  T: Send + Sync + Eq + Hash + Clone + Debug + TypeUuid + DeserializeOwned + 'static,
 {
-  fn init_atlas_animation(
+  // Restarts the animation when the request or the set changes, including when the set finishes
+  // loading or is hot reloaded. Turning to a variant with other frames carries on from the same
+  // point in it instead.
+  #[allow(clippy::type_complexity)]
+  fn init_animation<A: AnimationTarget>(
     mut commands: Commands,
-    mut qry: Query<
-      (
-        Entity,
-        Option<&mut PlayingAnimation>,
-        &RequestedAnimation<T>,
-        &mut TextureAtlasSprite,
-      ),
-      Or<(Changed<RequestedAnimation<T>>, Added<RequestedAnimation<T>>)>,
-    >,
-    defs: Res<AnimationSet<T>>,
+    mut set_events: EventReader<AssetEvent<AnimationSet<T>>>,
+    mut frame_events: EventWriter<AnimationFrameEvent<T>>,
+    mut qry: Query<(
+      Entity,
+      Option<&mut PlayingAnimation>,
+      &RequestedAnimation<T>,
+      ChangeTrackers<RequestedAnimation<T>>,
+      &Handle<AnimationSet<T>>,
+      ChangeTrackers<Handle<AnimationSet<T>>>,
+      Option<&Facing>,
+      Option<&AnimationPlayback>,
+      &mut A,
+      Option<&Handle<TextureAtlas>>,
+    )>,
+    sets: Res<Assets<AnimationSet<T>>>,
+    atlases: Res<Assets<TextureAtlas>>,
+    mut rng: ResMut<GameRng>,
   ) {
-    if !qry.is_empty() {
-      let mut rng = rand::thread_rng();
-
-      for (entity, maybe_anim, req, mut sprite) in qry.iter_mut() {
-        if let Some(def) = defs.animations.get(&req.play) {
-          let timer = Timer::from_seconds(1. / def.fps, true);
-          let start_frame = if def.random_start {
-            let between = Uniform::from(def.start..(def.end + 1));
-            between.sample(&mut rng)
-          } else {
-            def.start
-          };
-
-          if let Some(mut anim) = maybe_anim {
-            anim.timer = timer;
-            anim.start_frame = start_frame;
-            anim.complete = false;
-          } else {
-            commands.entity(entity).insert(PlayingAnimation {
-              timer,
-              start_frame,
-              complete: false,
-            });
-          }
+    let mut loaded = HashSet::new();
+    let mut reloaded = HashSet::new();
+    for ev in set_events.iter() {
+      match ev {
+        AssetEvent::Created { handle } => loaded.insert(handle.id),
+        AssetEvent::Modified { handle } => reloaded.insert(handle.id),
+        AssetEvent::Removed { .. } => false,
+      };
+    }
 
-          sprite.index = start_frame;
+    for (
+      entity,
+      maybe_anim,
+      req,
+      req_tracker,
+      handle,
+      handle_tracker,
+      facing,
+      playback,
+      mut target,
+      atlas,
+    ) in qry.iter_mut()
+    {
+      let base = match sets.get(handle).and_then(|set| set.get(&req.play)) {
+        Some(def) => def,
+        None => continue,
+      };
+      let variant = facing.and_then(|facing| base.variant_for(*facing));
+      let def = base.variant(variant);
+      let atlas_len = target
+        .frame_count()
+        .or_else(|| atlas.and_then(|a| atlases.get(a)).map(|a| a.len()));
+      // sets added before the entity was spawned are already playing once they are created
+      let first_load = loaded.contains(&handle.id) && maybe_anim.is_none();
+      let restart = req_tracker.is_changed()
+        || handle_tracker.is_changed()
+        || reloaded.contains(&handle.id)
+        || first_load;
+      if !restart {
+        if let Some(mut anim) = maybe_anim.filter(|anim| anim.variant != variant) {
+          if let Err(err) = def.validate(atlas_len) {
+            error!("can't play animation {:?}: {}", req.play, err);
+            continue;
+          }
+          // the same offset into the new variant's frames, like layers map their parent's frame
+          let from = base.variant(anim.variant);
+          let map = |frame: usize| (def.start + frame.saturating_sub(from.start)).min(def.end);
+          let frame = map(target.frame());
+          anim.start_frame = map(anim.start_frame);
+          anim.variant = variant;
+          anim.timer.set_duration(Duration::from_secs_f32(def.frame_duration(frame)));
+          target.set_frame(frame);
         }
+        continue;
+      }
+
+      if let Err(err) = def.validate(atlas_len) {
+        error!("can't play animation {:?}: {}", req.play, err);
+        continue;
+      }
+      let backwards = def.direction == AnimationDirection::Reverse;
+      let reverse = playback.map(|p| p.reverse).unwrap_or(false);
+      let start_frame = if def.random_start {
+        let between = Uniform::from(def.start..(def.end + 1));
+        between.sample(&mut *rng)
+      } else if backwards != reverse {
+        def.end
+      } else {
+        def.start
+      };
+      let timer = Timer::from_seconds(def.frame_duration(start_frame), true);
+
+      if let Some(mut anim) = maybe_anim {
+        anim.timer = timer;
+        anim.start_frame = start_frame;
+        anim.complete = false;
+        anim.backwards = backwards;
+        anim.variant = variant;
+      } else {
+        commands.entity(entity).insert(PlayingAnimation {
+          timer,
+          start_frame,
+          complete: false,
+          backwards,
+          variant,
+        });
       }
+
+      target.set_frame(start_frame);
+      send_frame_events(&mut frame_events, entity, &req.play, def, start_frame);
     }
   }
 
-  fn animate_sprites(
+  #[allow(clippy::type_complexity)]
+  fn animate<A: AnimationTarget>(
     time: Res<Time>,
-    defs: Res<AnimationSet<T>>,
+    time_scale: Res<AnimationTimeScale>,
+    sets: Res<Assets<AnimationSet<T>>>,
+    mut frame_events: EventWriter<AnimationFrameEvent<T>>,
+    mut finished_events: EventWriter<AnimationFinished<T>>,
     mut query: Query<(
+      Entity,
       &RequestedAnimation<T>,
+      &Handle<AnimationSet<T>>,
+      Option<&Facing>,
+      Option<&AnimationPlayback>,
       &mut PlayingAnimation,
-      &mut TextureAtlasSprite,
+      &mut A,
     )>,
   ) {
-    for (req, mut animation, mut sprite) in query.iter_mut() {
-      if let Some(def) = defs.animations.get(&req.play) {
+    for (entity, req, handle, facing, playback, mut animation, mut target) in query.iter_mut() {
+      let playback = playback.copied().unwrap_or_default();
+      if playback.paused {
+        continue;
+      }
+
+      if let Some((def, _)) = sets
+        .get(handle)
+        .and_then(|set| set.get_facing(&req.play, facing))
+      {
         if !animation.complete {
-          animation.timer.tick(time.delta());
-          if animation.timer.just_finished() {
-            let new_index = sprite.index + 1;
-            if new_index > def.end {
-              if let Some(repeat_from) = def.repeat_from {
-                sprite.index = repeat_from;
+          let scale = (time_scale.0 * playback.speed).max(0.);
+          animation.timer.tick(time.delta().mul_f32(scale));
+          // a long update can finish several frames, fast ones still show every frame's events
+          for _ in 0..animation.timer.times_finished_this_tick() {
+            if animation.complete {
+              break;
+            }
+            let backwards = animation.backwards != playback.reverse;
+            let frame = target.frame();
+            let at_edge = if backwards {
+              frame <= def.start
+            } else {
+              frame >= def.end
+            };
+
+            let frame = if !at_edge {
+              if backwards {
+                frame - 1
+              } else {
+                frame + 1
+              }
+            } else if def.direction == AnimationDirection::PingPong && def.start < def.end {
+              animation.backwards = !animation.backwards;
+              if backwards {
+                frame + 1
               } else {
-                sprite.index = def.start;
+                frame - 1
               }
+            } else if backwards {
+              def.end
+            } else if let Some(repeat_from) = def.repeat_from {
+              repeat_from
             } else {
-              sprite.index = new_index;
-            }
+              def.start
+            };
+            target.set_frame(frame);
+            // the timer keeps any overshoot, so only the length of the next frame changes
+            let next = def.frame_duration(frame);
+            animation.timer.set_duration(Duration::from_secs_f32(next));
+            send_frame_events(&mut frame_events, entity, &req.play, def, frame);
+
+            // the last frame is the one in the direction we're now heading
+            let last_frame = if animation.backwards != playback.reverse {
+              def.start
+            } else {
+              def.end
+            };
+            let complete = if def.random_start {
+              frame == animation.start_frame
+            } else {
+              frame == last_frame
+                && (def.direction != AnimationDirection::PingPong || animation.backwards)
+            };
 
-            if !def.repeat
-              && ((!def.random_start && sprite.index == def.end)
-                || sprite.index == animation.start_frame)
-            {
+            if !def.repeat && complete {
               animation.complete = true;
+              finished_events.send(AnimationFinished {
+                entity,
+                animation: req.play.clone(),
+              });
             }
           }
         }
@@ -134,3 +536,19 @@ where
     }
   }
 }
+
+fn send_frame_events<T: Clone + Send + Sync + 'static>(
+  writer: &mut EventWriter<AnimationFrameEvent<T>>,
+  entity: Entity,
+  animation: &T,
+  def: &AnimationDefinition,
+  frame: usize,
+) {
+  for name in def.events_at(frame) {
+    writer.send(AnimationFrameEvent {
+      entity,
+      animation: animation.clone(),
+      name: name.to_string(),
+    });
+  }
+}
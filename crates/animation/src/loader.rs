@@ -0,0 +1,64 @@
+use crate::{AnimationDefinition, AnimationSet};
+use bevy::{
+  asset::{AssetLoader, LoadContext, LoadedAsset},
+  reflect::TypeUuid,
+  utils::BoxedFuture,
+};
+use serde::de::DeserializeOwned;
+use std::{collections::HashMap, fmt::Debug, hash::Hash, marker::PhantomData};
+
+// `AssetLoader::extensions` borrows from the loader, so prefixed extensions are leaked. Loaders
+// are only created once when the plugin is built.
+pub(crate) fn prefixed(prefix: &str, extensions: &[&str]) -> Vec<&'static str> {
+  extensions
+    .iter()
+    .map(|ext| &*Box::leak(format!("{}.{}", prefix, ext).into_boxed_str()))
+    .collect()
+}
+
+/// Loads an [`AnimationSet`] from a RON map of animation keys to definitions.
+pub struct AnimationSetLoader<T> {
+  extensions: Vec<&'static str>,
+  phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for AnimationSetLoader<T> {
+  fn default() -> Self {
+    Self {
+      extensions: vec!["anim.ron"],
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<T> AnimationSetLoader<T> {
+  /// Only loads `<name>.<prefix>.anim.ron` files.
+  pub fn with_extension_prefix(mut self, prefix: &str) -> Self {
+    self.extensions = prefixed(prefix, &self.extensions);
+    self
+  }
+}
+
+impl<T> AssetLoader for AnimationSetLoader<T>
+where
+  T: Send + Sync + Eq + Hash + Clone + Debug + TypeUuid + DeserializeOwned + 'static,
+{
+  fn load<'a>(
+    &'a self,
+    bytes: &'a [u8],
+    load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+    Box::pin(async move {
+      let animations: HashMap<T, AnimationDefinition> = ron::de::from_bytes(bytes)?;
+      // the atlas isn't known here, frame bounds are checked again when playing
+      let set = AnimationSet { animations };
+      set.validate(None)?;
+      load_context.set_default_asset(LoadedAsset::new(set));
+      Ok(())
+    })
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &self.extensions
+  }
+}
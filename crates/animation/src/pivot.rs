@@ -0,0 +1,289 @@
+use crate::{loader::prefixed, AnimationDefinition, AnimationSet, ATLAS_LABEL, TEXTURE_LABEL};
+use anyhow::anyhow;
+use bevy::{
+  asset::{AssetLoader, LoadContext, LoadedAsset},
+  prelude::*,
+  reflect::TypeUuid,
+  render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+  utils::BoxedFuture,
+};
+use serde::de::{value::StrDeserializer, DeserializeOwned, IntoDeserializer};
+use std::{fmt::Debug, hash::Hash, marker::PhantomData};
+
+// Layout of a Pivot Animator 4 file holding a single stick figure, worked out from the files in
+// `assets_raw`. Anything else (several figures, custom figures) is rejected rather than guessed.
+const PIVOT_VERSION: u16 = 5;
+const HEADER_LEN: usize = 25;
+const FRAME_COUNT_OFFSET: usize = 21;
+const FRAME_LEN: usize = 134;
+const FRAME_SCALE_OFFSET: usize = 13;
+const FRAME_ANGLES_OFFSET: usize = 22;
+const FRAME_ORIGIN_OFFSET: usize = FRAME_ANGLES_OFFSET + STICK_FIGURE_SEGMENTS * 8;
+const TRAILER_LEN: usize = 5;
+
+pub const STICK_FIGURE_SEGMENTS: usize = 12;
+
+/// One frame of a Pivot figure: its origin joint and the absolute angle in radians of every
+/// segment. Coordinates are Pivot's canvas pixels, y pointing down.
+#[derive(Clone, Debug)]
+pub struct PivotPose {
+  pub origin: Vec2,
+  pub scale: f32,
+  pub angles: Vec<f32>,
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+  let mut bytes = [0; N];
+  bytes.copy_from_slice(&data[offset..offset + N]);
+  bytes
+}
+
+/// Reads the poses of a `.piv` file, one per frame.
+pub fn read_pivot(bytes: &[u8]) -> Result<Vec<PivotPose>, anyhow::Error> {
+  let data = miniz_oxide::inflate::decompress_to_vec_zlib(bytes)
+    .map_err(|status| anyhow!("not a pivot file: {:?}", status))?;
+  if data.len() < HEADER_LEN {
+    return Err(anyhow!("pivot file is truncated"));
+  }
+  let version = u16::from_le_bytes(read(&data, 0));
+  if version != PIVOT_VERSION {
+    return Err(anyhow!("unsupported pivot file version {}", version));
+  }
+  let frame_count = u32::from_le_bytes(read(&data, FRAME_COUNT_OFFSET)) as usize;
+  if frame_count == 0 || data.len() != HEADER_LEN + frame_count * FRAME_LEN + TRAILER_LEN {
+    return Err(anyhow!(
+      "only files with a single default stick figure are supported"
+    ));
+  }
+
+  Ok(
+    (0..frame_count)
+      .map(|i| {
+        let frame = &data[HEADER_LEN + i * FRAME_LEN..HEADER_LEN + (i + 1) * FRAME_LEN];
+        PivotPose {
+          origin: Vec2::new(
+            f32::from_le_bytes(read(frame, FRAME_ORIGIN_OFFSET)),
+            f32::from_le_bytes(read(frame, FRAME_ORIGIN_OFFSET + 4)),
+          ),
+          scale: f32::from_le_bytes(read(frame, FRAME_SCALE_OFFSET)),
+          angles: (0..STICK_FIGURE_SEGMENTS)
+            .map(|s| f64::from_le_bytes(read(frame, FRAME_ANGLES_OFFSET + s * 8)) as f32)
+            .collect(),
+        }
+      })
+      .collect(),
+  )
+}
+
+/// A segment starts at joint `from` and ends at the joint with its own index + 1. Joint 0 is the
+/// figure's origin.
+#[derive(Clone, Copy, Debug)]
+pub struct PivotSegment {
+  pub from: usize,
+  pub length: f32,
+  // drawn as a circle with the segment as its diameter
+  pub head: bool,
+}
+
+/// How the segments of a figure connect, the files only store their angles.
+#[derive(Clone, Debug)]
+pub struct PivotRig {
+  pub segments: Vec<PivotSegment>,
+}
+
+impl PivotRig {
+  /// Pivot's default stick figure. The leg lengths were measured by keeping the feet planted in
+  /// `idle.piv`, the rest are approximate.
+  pub fn stick_figure() -> Self {
+    let segment = |from, length| PivotSegment {
+      from,
+      length,
+      head: false,
+    };
+    Self {
+      segments: vec![
+        // torso, neck and head
+        segment(0, 45.),
+        segment(1, 8.),
+        PivotSegment {
+          head: true,
+          ..segment(2, 20.)
+        },
+        // runs back down the neck, the arms hang off its end
+        segment(2, 8.),
+        segment(4, 35.),
+        segment(5, 35.),
+        segment(4, 35.),
+        segment(7, 35.),
+        segment(0, 47.),
+        segment(9, 50.),
+        segment(0, 47.),
+        segment(11, 50.),
+      ],
+    }
+  }
+
+  /// Joint positions of `pose`, starting with its origin.
+  pub fn joints(&self, pose: &PivotPose) -> Vec<Vec2> {
+    let mut joints = vec![pose.origin];
+    for (segment, angle) in self.segments.iter().zip(pose.angles.iter()) {
+      let start = joints[segment.from];
+      joints.push(start + Vec2::new(angle.cos(), angle.sin()) * segment.length * pose.scale);
+    }
+    joints
+  }
+}
+
+fn distance_to_segment(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+  let ab = b - a;
+  let t = ((p - a).dot(ab) / ab.length_squared().max(f32::EPSILON)).clamp(0., 1.);
+  p.distance(a + ab * t)
+}
+
+/// Loads `.piv` files by drawing every frame of the figure into a single row atlas, available as
+/// labeled `atlas` and `texture` assets. The file name is the key of the only animation in the
+/// set, so `idle.piv` and `idle.player.piv` load as the `idle` animation.
+pub struct PivotLoader<T> {
+  pub rig: PivotRig,
+  // in pixels, the width follows from the figure's extent over all frames
+  pub frame_height: u32,
+  // line width in pivot units
+  pub thickness: f32,
+  pub color: [u8; 4],
+  // pivot's own playback speed isn't read from the file
+  pub fps: f32,
+  extensions: Vec<&'static str>,
+  phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for PivotLoader<T> {
+  fn default() -> Self {
+    Self {
+      rig: PivotRig::stick_figure(),
+      frame_height: 48,
+      thickness: 6.,
+      color: [230, 230, 230, 255],
+      fps: 12.,
+      extensions: vec!["piv"],
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<T> PivotLoader<T> {
+  /// Only loads `<name>.<prefix>.piv` files.
+  pub fn with_extension_prefix(mut self, prefix: &str) -> Self {
+    self.extensions = prefixed(prefix, &self.extensions);
+    self
+  }
+
+  // Frames share the bounds of the whole animation so the figure doesn't jump around.
+  fn render(&self, poses: &[PivotPose]) -> (Vec<u8>, u32, u32) {
+    let frames: Vec<_> = poses.iter().map(|pose| self.rig.joints(pose)).collect();
+    let pad = self.thickness / 2.;
+    let (mut min, mut max) = (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN));
+    for (pose, joints) in poses.iter().zip(frames.iter()) {
+      for (i, segment) in self.rig.segments.iter().enumerate() {
+        let (a, b) = (joints[segment.from], joints[i + 1]);
+        let reach = if segment.head {
+          segment.length * pose.scale / 2. + pad
+        } else {
+          pad
+        };
+        min = min.min(a.min(b) - reach);
+        max = max.max(a.max(b) + reach);
+      }
+    }
+
+    let scale = self.frame_height as f32 / (max.y - min.y).max(1.);
+    let width = ((max.x - min.x) * scale).ceil().max(1.) as u32;
+    let height = self.frame_height;
+    let stride = (width as usize * frames.len()) * 4;
+    let mut data = vec![0; stride * height as usize];
+
+    for (frame, (pose, joints)) in poses.iter().zip(frames.iter()).enumerate() {
+      for y in 0..height {
+        for x in 0..width {
+          let p = min + Vec2::new(x as f32 + 0.5, y as f32 + 0.5) / scale;
+          let covered = self.rig.segments.iter().enumerate().any(|(i, segment)| {
+            let (a, b) = (joints[segment.from], joints[i + 1]);
+            if segment.head {
+              p.distance((a + b) / 2.) <= segment.length * pose.scale / 2.
+            } else {
+              distance_to_segment(p, a, b) <= pad
+            }
+          });
+          if covered {
+            let i = y as usize * stride + (frame * width as usize + x as usize) * 4;
+            data[i..i + 4].copy_from_slice(&self.color);
+          }
+        }
+      }
+    }
+
+    (data, width, height)
+  }
+}
+
+impl<T> AssetLoader for PivotLoader<T>
+where
+  T: Send + Sync + Eq + Hash + Clone + Debug + TypeUuid + DeserializeOwned + 'static,
+{
+  fn load<'a>(
+    &'a self,
+    bytes: &'a [u8],
+    load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+    Box::pin(async move {
+      let name = load_context
+        .path()
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .unwrap_or_default()
+        .to_string();
+      let de: StrDeserializer<serde::de::value::Error> = name.as_str().into_deserializer();
+      let key = T::deserialize(de)
+        .map_err(|_| anyhow!("`{}` doesn't name an animation", name))?;
+
+      let poses = read_pivot(bytes)?;
+      let (data, width, height) = self.render(&poses);
+      let image = Image::new(
+        Extent3d {
+          width: width * poses.len() as u32,
+          height,
+          depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+      );
+      let texture = load_context.set_labeled_asset(TEXTURE_LABEL, LoadedAsset::new(image));
+      let atlas = TextureAtlas::from_grid(
+        texture,
+        Vec2::new(width as f32, height as f32),
+        poses.len(),
+        1,
+      );
+      load_context.set_labeled_asset(ATLAS_LABEL, LoadedAsset::new(atlas));
+
+      let set = AnimationSet::new().with(
+        key,
+        AnimationDefinition {
+          start: 0,
+          end: poses.len() - 1,
+          fps: self.fps,
+          repeat: true,
+          ..Default::default()
+        },
+      );
+      set.validate(Some(poses.len()))?;
+      load_context.set_default_asset(LoadedAsset::new(set));
+      Ok(())
+    })
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &self.extensions
+  }
+}
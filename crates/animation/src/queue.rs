@@ -0,0 +1,103 @@
+use crate::{AnimationSet, PlayingAnimation, RequestedAnimation};
+use bevy::{prelude::*, reflect::TypeUuid};
+use std::{collections::VecDeque, fmt::Debug, hash::Hash};
+
+/// Plays animations in sequence on top of whatever is currently requested, then returns to it.
+/// The next one starts when the current one completes, so repeating animations and keys missing
+/// from the set are skipped with a warning.
+#[derive(Component)]
+pub struct AnimationQueue<T> {
+  queue: VecDeque<T>,
+  // the animation to return to, set while the queue is in control
+  base: Option<T>,
+  interrupt: bool,
+}
+
+impl<T> Default for AnimationQueue<T> {
+  fn default() -> Self {
+    Self {
+      queue: VecDeque::new(),
+      base: None,
+      interrupt: false,
+    }
+  }
+}
+
+impl<T> AnimationQueue<T> {
+  /// Plays `animation` right away, dropping anything queued, then resumes the base animation.
+  pub fn play_once(&mut self, animation: T) {
+    self.queue.clear();
+    self.queue.push_back(animation);
+    self.interrupt = true;
+  }
+
+  /// Plays `animation` after the ones already queued.
+  pub fn enqueue(&mut self, animation: T) {
+    self.queue.push_back(animation);
+  }
+
+  /// Drops queued animations, the one playing still returns to the base when it finishes.
+  pub fn clear(&mut self) {
+    self.queue.clear();
+    self.interrupt = false;
+  }
+
+  pub fn is_playing(&self) -> bool {
+    self.base.is_some() || !self.queue.is_empty()
+  }
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn advance_queues<T>(
+  sets: Res<Assets<AnimationSet<T>>>,
+  mut qry: Query<(
+    &mut AnimationQueue<T>,
+    &mut RequestedAnimation<T>,
+    &Handle<AnimationSet<T>>,
+    Option<&PlayingAnimation>,
+  )>,
+) where
+  T: Send + Sync + Eq + Hash + Clone + Debug + TypeUuid + 'static,
+{
+  for (mut queue, mut req, handle, playing) in qry.iter_mut() {
+    let finished = playing.map(|p| p.complete).unwrap_or(false);
+    let starting = queue.base.is_none() && !queue.queue.is_empty();
+    if !starting && !queue.interrupt && (queue.base.is_none() || !finished) {
+      continue;
+    }
+    // queued keys are checked against the set, so wait for it to load
+    let set = match sets.get(handle) {
+      Some(set) => set,
+      None => continue,
+    };
+
+    // a repeating animation never completes and a missing one never starts, either would leave
+    // the queue waiting forever
+    let next = loop {
+      match queue.queue.pop_front() {
+        Some(next) => match set.get(&next) {
+          Some(def) if !def.repeat => break Some(next),
+          Some(_) => warn!("skipping queued animation {:?}, it repeats", next),
+          None => warn!("skipping queued animation {:?}, it isn't in the set", next),
+        },
+        None => break None,
+      }
+    };
+
+    queue.interrupt = false;
+    match next {
+      Some(next) => {
+        if queue.base.is_none() {
+          queue.base = Some(req.play.clone());
+        }
+        req.play = next;
+      }
+      // only touch the request when returning, so a queue of skipped entries doesn't restart it
+      None => {
+        if let Some(base) = queue.base.take() {
+          req.play = base;
+        }
+      }
+    }
+  }
+}
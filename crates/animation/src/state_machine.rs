@@ -0,0 +1,209 @@
+use crate::{AnimationQueue, PlayingAnimation, RequestedAnimation};
+use bevy::prelude::*;
+use std::{collections::HashMap, hash::Hash};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamValue {
+  Bool(bool),
+  Float(f32),
+}
+
+/// Values set by gameplay (grounded, velocity, attacking...) that state machine conditions read.
+#[derive(Component, Clone, Default)]
+pub struct AnimationParams {
+  values: HashMap<String, ParamValue>,
+}
+
+impl AnimationParams {
+  pub fn set_bool(&mut self, name: &str, value: bool) {
+    self.set(name, ParamValue::Bool(value));
+  }
+
+  pub fn set_float(&mut self, name: &str, value: f32) {
+    self.set(name, ParamValue::Float(value));
+  }
+
+  pub fn get(&self, name: &str) -> Option<ParamValue> {
+    self.values.get(name).copied()
+  }
+
+  // avoid triggering change detection when nothing changed
+  fn set(&mut self, name: &str, value: ParamValue) {
+    if self.values.get(name) != Some(&value) {
+      self.values.insert(name.to_string(), value);
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub enum Condition {
+  Bool(String, bool),
+  GreaterThan(String, f32),
+  LessThan(String, f32),
+  // the current state's animation has completed, never true for repeating animations
+  Finished,
+  Not(Box<Condition>),
+  Any(Vec<Condition>),
+}
+
+impl Condition {
+  pub fn is(name: &str, value: bool) -> Self {
+    Self::Bool(name.to_string(), value)
+  }
+
+  pub fn greater(name: &str, value: f32) -> Self {
+    Self::GreaterThan(name.to_string(), value)
+  }
+
+  pub fn less(name: &str, value: f32) -> Self {
+    Self::LessThan(name.to_string(), value)
+  }
+
+  // missing params are never satisfied
+  fn holds(&self, params: Option<&AnimationParams>, finished: bool) -> bool {
+    let param = |name: &str| params.and_then(|p| p.get(name));
+    match self {
+      Condition::Bool(name, expected) => param(name) == Some(ParamValue::Bool(*expected)),
+      Condition::GreaterThan(name, value) => {
+        matches!(param(name), Some(ParamValue::Float(v)) if v > *value)
+      }
+      Condition::LessThan(name, value) => {
+        matches!(param(name), Some(ParamValue::Float(v)) if v < *value)
+      }
+      Condition::Finished => finished,
+      Condition::Not(inner) => !inner.holds(params, finished),
+      Condition::Any(conditions) => conditions.iter().any(|c| c.holds(params, finished)),
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct Transition<T> {
+  pub to: T,
+  // all must hold
+  pub conditions: Vec<Condition>,
+}
+
+#[derive(Clone)]
+struct StateDefinition<T> {
+  priority: u32,
+  transitions: Vec<Transition<T>>,
+}
+
+/// Picks the animation to request from a set of states. Each state plays the animation with the
+/// same key. A state can only be left before its animation finishes by moving to a state with at
+/// least the same priority, so an attack can't be cut short by running but can be by getting hit.
+#[derive(Component, Clone)]
+pub struct AnimationStateMachine<T> {
+  states: HashMap<T, StateDefinition<T>>,
+  any_state: Vec<Transition<T>>,
+  current: T,
+}
+
+impl<T> AnimationStateMachine<T>
+where
+  T: Eq + Hash + Clone,
+{
+  pub fn new(initial: T) -> Self {
+    Self {
+      states: HashMap::new(),
+      any_state: Vec::new(),
+      current: initial,
+    }
+  }
+
+  pub fn with_state(mut self, state: T, priority: u32) -> Self {
+    self.state_mut(state).priority = priority;
+    self
+  }
+
+  pub fn with_transition(mut self, from: T, to: T, conditions: Vec<Condition>) -> Self {
+    self
+      .state_mut(from)
+      .transitions
+      .push(Transition { to, conditions });
+    self
+  }
+
+  /// A transition that is checked from every state other than its target.
+  pub fn with_any_transition(mut self, to: T, conditions: Vec<Condition>) -> Self {
+    self.any_state.push(Transition { to, conditions });
+    self
+  }
+
+  pub fn current(&self) -> &T {
+    &self.current
+  }
+
+  fn state_mut(&mut self, state: T) -> &mut StateDefinition<T> {
+    self.states.entry(state).or_insert_with(|| StateDefinition {
+      priority: 0,
+      transitions: Vec::new(),
+    })
+  }
+
+  fn priority(&self, state: &T) -> u32 {
+    self.states.get(state).map(|s| s.priority).unwrap_or(0)
+  }
+
+  fn next(&self, params: Option<&AnimationParams>, finished: bool) -> Option<T> {
+    let current_priority = self.priority(&self.current);
+    let mut candidates: Vec<_> = self
+      .states
+      .get(&self.current)
+      .into_iter()
+      .flat_map(|s| s.transitions.iter())
+      .chain(self.any_state.iter().filter(|t| t.to != self.current))
+      .collect();
+    // stable, so declaration order breaks ties
+    candidates.sort_by_key(|t| std::cmp::Reverse(self.priority(&t.to)));
+
+    candidates
+      .into_iter()
+      .filter(|t| finished || self.priority(&t.to) >= current_priority)
+      .find(|t| t.conditions.iter().all(|c| c.holds(params, finished)))
+      .map(|t| t.to.clone())
+  }
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn drive_state_machines<T>(
+  mut commands: Commands,
+  mut qry: Query<(
+    Entity,
+    &mut AnimationStateMachine<T>,
+    Option<&AnimationParams>,
+    Option<&mut RequestedAnimation<T>>,
+    Option<&PlayingAnimation>,
+    Option<&AnimationQueue<T>>,
+  )>,
+) where
+  T: Send + Sync + Eq + Hash + Clone + 'static,
+{
+  for (entity, mut machine, params, req, playing, queue) in qry.iter_mut() {
+    // queued animations take over until they return to the base animation
+    if queue.map(|q| q.is_playing()).unwrap_or(false) {
+      continue;
+    }
+
+    let finished = playing.map(|p| p.complete).unwrap_or(false);
+    let next = machine.next(params, finished);
+
+    match (next, req) {
+      (Some(next), Some(mut req)) => {
+        machine.current = next.clone();
+        req.play = next;
+      }
+      (Some(next), None) => {
+        machine.current = next.clone();
+        commands.entity(entity).insert(RequestedAnimation { play: next });
+      }
+      (None, None) => {
+        commands.entity(entity).insert(RequestedAnimation {
+          play: machine.current.clone(),
+        });
+      }
+      (None, Some(_)) => {}
+    }
+  }
+}
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+
+/// A component whose displayed frame is driven by the animation systems. Frame numbers are the
+/// same ones `AnimationDefinition` uses, so an entity should only have one target.
+pub trait AnimationTarget: Component {
+  fn frame(&self) -> usize;
+
+  fn set_frame(&mut self, frame: usize);
+
+  /// The number of frames the target can show, when it knows without a `Handle<TextureAtlas>`.
+  fn frame_count(&self) -> Option<usize> {
+    None
+  }
+}
+
+impl AnimationTarget for TextureAtlasSprite {
+  fn frame(&self) -> usize {
+    self.index
+  }
+
+  fn set_frame(&mut self, frame: usize) {
+    self.index = frame;
+  }
+}
+
+/// Animates entities that show a whole image rather than an atlas region, like UI nodes or plain
+/// sprites. The current frame is copied into the entity's `UiImage` or `Handle<Image>`.
+#[derive(Component, Clone, Default)]
+pub struct ImageSequence {
+  pub frames: Vec<Handle<Image>>,
+  pub index: usize,
+}
+
+impl ImageSequence {
+  pub fn new(frames: Vec<Handle<Image>>) -> Self {
+    Self { frames, index: 0 }
+  }
+}
+
+impl AnimationTarget for ImageSequence {
+  fn frame(&self) -> usize {
+    self.index
+  }
+
+  fn set_frame(&mut self, frame: usize) {
+    self.index = frame;
+  }
+
+  fn frame_count(&self) -> Option<usize> {
+    Some(self.frames.len())
+  }
+}
+
+pub(crate) fn apply_image_sequences(
+  mut ui_images: Query<(&ImageSequence, &mut UiImage), Changed<ImageSequence>>,
+  mut sprites: Query<(&ImageSequence, &mut Handle<Image>), Changed<ImageSequence>>,
+) {
+  for (sequence, mut image) in ui_images.iter_mut() {
+    if let Some(frame) = sequence.frames.get(sequence.index) {
+      if image.0 != *frame {
+        image.0 = frame.clone();
+      }
+    }
+  }
+  for (sequence, mut image) in sprites.iter_mut() {
+    if let Some(frame) = sequence.frames.get(sequence.index) {
+      if *image != *frame {
+        *image = frame.clone();
+      }
+    }
+  }
+}
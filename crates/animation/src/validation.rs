@@ -0,0 +1,140 @@
+use crate::{AnimationDefinition, AnimationSet};
+use std::fmt::{self, Debug, Display};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnimationError {
+  StartAfterEnd { start: usize, end: usize },
+  RepeatFromOutOfRange { repeat_from: usize },
+  FrameOutsideAtlas { frame: usize, atlas_len: usize },
+  InvalidFps(f32),
+  InvalidFrameDuration { frame: usize, duration: f32 },
+  EventOutOfRange { name: String, frame: usize },
+  BoxesOutOfRange { frame: usize },
+}
+
+impl Display for AnimationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      AnimationError::StartAfterEnd { start, end } => {
+        write!(f, "start frame {} is after end frame {}", start, end)
+      }
+      AnimationError::RepeatFromOutOfRange { repeat_from } => {
+        write!(f, "repeat_from frame {} is not between start and end", repeat_from)
+      }
+      AnimationError::FrameOutsideAtlas { frame, atlas_len } => {
+        write!(f, "frame {} is outside the atlas of {} frames", frame, atlas_len)
+      }
+      AnimationError::InvalidFps(fps) => write!(f, "fps must be positive, got {}", fps),
+      AnimationError::InvalidFrameDuration { frame, duration } => {
+        write!(f, "frame {} has a non-positive duration of {}", frame, duration)
+      }
+      AnimationError::EventOutOfRange { name, frame } => {
+        write!(f, "event `{}` is on frame {}, which the animation never shows", name, frame)
+      }
+      AnimationError::BoxesOutOfRange { frame } => {
+        write!(f, "collision boxes are set for frame {}, which the animation never shows", frame)
+      }
+    }
+  }
+}
+
+impl std::error::Error for AnimationError {}
+
+impl AnimationDefinition {
+  /// Checks the frame range is well formed and, when the atlas length is known, that every frame
+  /// exists in it. Directional variants are checked as well.
+  pub fn validate(&self, atlas_len: Option<usize>) -> Result<(), AnimationError> {
+    if self.start > self.end {
+      return Err(AnimationError::StartAfterEnd {
+        start: self.start,
+        end: self.end,
+      });
+    }
+    let in_range = |frame: usize| (self.start..=self.end).contains(&frame);
+
+    if let Some(repeat_from) = self.repeat_from.filter(|f| !in_range(*f)) {
+      return Err(AnimationError::RepeatFromOutOfRange { repeat_from });
+    }
+    if let Some(atlas_len) = atlas_len.filter(|len| self.end >= *len) {
+      return Err(AnimationError::FrameOutsideAtlas {
+        frame: self.end,
+        atlas_len,
+      });
+    }
+    // fps is only a fallback when every frame has a duration
+    let has_all_durations = self
+      .frame_durations
+      .as_ref()
+      .map(|d| d.len() > self.end - self.start)
+      .unwrap_or(false);
+    if !has_all_durations && (self.fps.is_nan() || self.fps <= 0.) {
+      return Err(AnimationError::InvalidFps(self.fps));
+    }
+    if let Some((i, duration)) = self
+      .frame_durations
+      .iter()
+      .flatten()
+      .enumerate()
+      .find(|(_, d)| d.is_nan() || **d <= 0.)
+    {
+      return Err(AnimationError::InvalidFrameDuration {
+        frame: self.start + i,
+        duration: *duration,
+      });
+    }
+    if let Some(ev) = self.events.iter().find(|ev| !in_range(ev.frame)) {
+      return Err(AnimationError::EventOutOfRange {
+        name: ev.name.clone(),
+        frame: ev.frame,
+      });
+    }
+    if let Some(frame) = self.boxes.keys().find(|f| !in_range(**f)) {
+      return Err(AnimationError::BoxesOutOfRange { frame: *frame });
+    }
+
+    self
+      .variants
+      .values()
+      .try_for_each(|variant| variant.validate(atlas_len))
+  }
+}
+
+/// Every invalid definition of a set, with its key.
+#[derive(Clone, Debug)]
+pub struct InvalidAnimationSet(pub Vec<(String, AnimationError)>);
+
+impl Display for InvalidAnimationSet {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "invalid animations:")?;
+    for (key, error) in self.0.iter() {
+      write!(f, "\n  {}: {}", key, error)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::error::Error for InvalidAnimationSet {}
+
+impl<T> AnimationSet<T>
+where
+  T: Debug,
+{
+  pub fn validate(&self, atlas_len: Option<usize>) -> Result<(), InvalidAnimationSet> {
+    let errors: Vec<_> = self
+      .animations
+      .iter()
+      .filter_map(|(key, def)| {
+        def
+          .validate(atlas_len)
+          .err()
+          .map(|err| (format!("{:?}", key), err))
+      })
+      .collect();
+
+    if errors.is_empty() {
+      Ok(())
+    } else {
+      Err(InvalidAnimationSet(errors))
+    }
+  }
+}
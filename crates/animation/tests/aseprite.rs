@@ -0,0 +1,41 @@
+use game_animation::*;
+
+fn sheet(frames: &str) -> String {
+  format!(
+    r#"{{
+      "frames": {},
+      "meta": {{ "image": "player.png", "size": {{ "w": 384, "h": 32 }}, "frameTags": [] }}
+    }}"#,
+    frames
+  )
+}
+
+fn frame(i: usize) -> String {
+  format!(
+    r#"{{ "frame": {{ "x": {}, "y": 0, "w": 32, "h": 32 }}, "duration": {} }}"#,
+    i * 32,
+    100 + i
+  )
+}
+
+#[test]
+fn hash_frames_are_ordered_by_frame_number() {
+  let entries: Vec<_> = (0..12)
+    .map(|i| format!(r#""player {}.aseprite": {}"#, i, frame(i)))
+    .collect();
+  let frames =
+    read_sheet_frames(sheet(&format!("{{ {} }}", entries.join(", "))).as_bytes()).unwrap();
+
+  let durations: Vec<_> = frames.iter().map(|f| f.duration).collect();
+  assert_eq!(durations, (100..112).collect::<Vec<_>>());
+  assert_eq!(frames[10].rect.min.x, 320.);
+}
+
+#[test]
+fn array_frames_keep_their_order() {
+  let entries: Vec<_> = [2, 0, 1].into_iter().map(frame).collect();
+  let frames = read_sheet_frames(sheet(&format!("[ {} ]", entries.join(", "))).as_bytes()).unwrap();
+
+  let durations: Vec<_> = frames.iter().map(|f| f.duration).collect();
+  assert_eq!(durations, vec![102, 100, 101]);
+}
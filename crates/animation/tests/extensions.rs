@@ -0,0 +1,38 @@
+use bevy::{asset::AssetLoader, reflect::TypeUuid};
+use game_animation::*;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, TypeUuid)]
+#[uuid = "4b2e8c17-93d5-4a6f-8e21-d7f05c3b9a64"]
+enum Key {
+  Idle,
+}
+
+#[test]
+fn loaders_keep_the_plain_extensions_by_default() {
+  assert_eq!(
+    AsepriteLoader::<Key>::default().extensions(),
+    ["aseprite", "ase"]
+  );
+  assert_eq!(
+    AsepriteJsonLoader::<Key>::default().extensions(),
+    ["aseprite.json"]
+  );
+  assert_eq!(
+    AnimationSetLoader::<Key>::default().extensions(),
+    ["anim.ron"]
+  );
+  assert_eq!(PivotLoader::<Key>::default().extensions(), ["piv"]);
+}
+
+#[test]
+fn prefixed_loaders_only_claim_their_own_files() {
+  let aseprite = AsepriteLoader::<Key>::default().with_extension_prefix("player");
+  assert_eq!(aseprite.extensions(), ["player.aseprite", "player.ase"]);
+  let json = AsepriteJsonLoader::<Key>::default().with_extension_prefix("player");
+  assert_eq!(json.extensions(), ["player.aseprite.json"]);
+  let ron = AnimationSetLoader::<Key>::default().with_extension_prefix("player");
+  assert_eq!(ron.extensions(), ["player.anim.ron"]);
+  let pivot = PivotLoader::<Key>::default().with_extension_prefix("player");
+  assert_eq!(pivot.extensions(), ["player.piv"]);
+}
@@ -0,0 +1,37 @@
+use game_animation::*;
+
+const IDLE: &[u8] = include_bytes!("../../../assets_raw/idle.piv");
+const RUN: &[u8] = include_bytes!("../../../assets_raw/run.piv");
+const ATTACK: &[u8] = include_bytes!("../../../assets_raw/attack.piv");
+
+#[test]
+fn reads_every_frame_of_the_raw_animations() {
+  for (bytes, frames) in [(IDLE, 4), (RUN, 7), (ATTACK, 9)] {
+    let poses = read_pivot(bytes).unwrap();
+    assert_eq!(poses.len(), frames);
+    assert!(poses
+      .iter()
+      .all(|pose| pose.angles.len() == STICK_FIGURE_SEGMENTS && pose.scale == 1.));
+  }
+}
+
+#[test]
+fn idle_keeps_its_feet_on_the_ground() {
+  let rig = PivotRig::stick_figure();
+  let feet: Vec<_> = read_pivot(IDLE)
+    .unwrap()
+    .iter()
+    .flat_map(|pose| {
+      let joints = rig.joints(pose);
+      [joints[10].y, joints[12].y]
+    })
+    .collect();
+
+  let ground = feet[0];
+  assert!(feet.iter().all(|y| (y - ground).abs() < 1.), "{:?}", feet);
+}
+
+#[test]
+fn rejects_other_files() {
+  assert!(read_pivot(b"not a pivot file").is_err());
+}
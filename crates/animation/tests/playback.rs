@@ -0,0 +1,809 @@
+use bevy::{
+  asset::{AssetPlugin, HandleId},
+  prelude::*,
+  reflect::TypeUuid,
+};
+use game_animation::*;
+use game_utils::{GameRng, SteppedApp, STEP};
+use heron::{CollisionLayers, SensorShape};
+use rand::RngCore;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, TypeUuid)]
+#[uuid = "2f6c9a7e-0d41-4c2b-8e1f-5a3b7c9d1e20"]
+enum Key {
+  Idle,
+  Run,
+  Attack,
+  Hurt,
+}
+
+// every step finishes exactly one frame
+const FRAME: f32 = STEP;
+
+struct Harness {
+  app: SteppedApp,
+}
+
+impl Harness {
+  fn new(seed: u64, set: AnimationSet<Key>) -> (Self, Handle<AnimationSet<Key>>) {
+    let mut app = SteppedApp::new();
+    app
+      .insert_resource(GameRng::seeded(seed))
+      .add_plugin(AssetPlugin)
+      .add_asset::<TextureAtlas>()
+      .add_plugin(game_animation::AnimationPlugin::<Key>::default());
+
+    let handle = app
+      .world
+      .resource_mut::<Assets<AnimationSet<Key>>>()
+      .add(set);
+    (Self { app }, handle)
+  }
+
+  fn spawn(&mut self, handle: &Handle<AnimationSet<Key>>, key: Key) -> Entity {
+    let entity = self
+      .app
+      .world
+      .spawn()
+      .insert(RequestedAnimation::new(key))
+      .insert(handle.clone())
+      .insert(TextureAtlasSprite::default())
+      .id();
+    self.settle();
+    entity
+  }
+
+  fn settle(&mut self) {
+    self.app.settle();
+  }
+
+  fn step(&mut self) {
+    self.app.step();
+  }
+
+  fn frame(&self, entity: Entity) -> usize {
+    self
+      .app
+      .world
+      .get::<TextureAtlasSprite>(entity)
+      .unwrap()
+      .index
+  }
+
+  fn frames(&mut self, entity: Entity, steps: usize) -> Vec<usize> {
+    (0..steps)
+      .map(|_| {
+        self.step();
+        self.frame(entity)
+      })
+      .collect()
+  }
+
+  fn requested(&self, entity: Entity) -> Key {
+    self
+      .app
+      .world
+      .get::<RequestedAnimation<Key>>(entity)
+      .unwrap()
+      .play
+  }
+
+  fn params(&mut self, entity: Entity) -> Mut<'_, AnimationParams> {
+    self.app.world.get_mut::<AnimationParams>(entity).unwrap()
+  }
+
+  fn queue(&mut self, entity: Entity) -> Mut<'_, AnimationQueue<Key>> {
+    self
+      .app
+      .world
+      .get_mut::<AnimationQueue<Key>>(entity)
+      .unwrap()
+  }
+
+  fn complete(&self, entity: Entity) -> bool {
+    self
+      .app
+      .world
+      .get::<PlayingAnimation>(entity)
+      .unwrap()
+      .complete
+  }
+
+  // the names sent since the last call
+  fn frame_events(&mut self) -> Vec<(Entity, String)> {
+    let mut events = self
+      .app
+      .world
+      .resource_mut::<Events<AnimationFrameEvent<Key>>>();
+    events.drain().map(|ev| (ev.entity, ev.name)).collect()
+  }
+
+  fn finished_events(&self) -> Vec<(Entity, Key)> {
+    let events = self.app.world.resource::<Events<AnimationFinished<Key>>>();
+    events
+      .get_reader()
+      .iter(events)
+      .map(|ev| (ev.entity, ev.animation))
+      .collect()
+  }
+}
+
+fn definition(start: usize, end: usize, repeat: bool) -> AnimationDefinition {
+  AnimationDefinition {
+    start,
+    end,
+    fps: 1. / FRAME,
+    repeat,
+    ..Default::default()
+  }
+}
+
+#[test]
+fn repeating_animation_wraps_to_start() {
+  let (mut h, handle) = Harness::new(
+    0,
+    AnimationSet::new().with(Key::Run, definition(2, 4, true)),
+  );
+  let entity = h.spawn(&handle, Key::Run);
+
+  assert_eq!(h.frame(entity), 2);
+  assert_eq!(h.frames(entity, 6), vec![3, 4, 2, 3, 4, 2]);
+  assert!(!h.complete(entity));
+}
+
+#[test]
+fn repeat_from_skips_the_intro_frames() {
+  let def = AnimationDefinition {
+    repeat_from: Some(2),
+    ..definition(0, 3, true)
+  };
+  let (mut h, handle) = Harness::new(0, AnimationSet::new().with(Key::Run, def));
+  let entity = h.spawn(&handle, Key::Run);
+
+  assert_eq!(h.frames(entity, 7), vec![1, 2, 3, 2, 3, 2, 3]);
+}
+
+#[test]
+fn frame_durations_drive_the_timer() {
+  let def = AnimationDefinition {
+    frame_durations: Some(vec![2. * FRAME, FRAME, 3. * FRAME]),
+    ..definition(2, 4, true)
+  };
+  let (mut h, handle) = Harness::new(0, AnimationSet::new().with(Key::Run, def));
+  let entity = h.spawn(&handle, Key::Run);
+
+  // the first frame already holds for its own duration
+  assert_eq!(h.frame(entity), 2);
+  assert_eq!(h.frames(entity, 7), vec![2, 3, 4, 4, 4, 2, 2]);
+}
+
+#[test]
+fn non_repeating_animation_completes_on_its_last_frame() {
+  let (mut h, handle) = Harness::new(
+    0,
+    AnimationSet::new().with(Key::Attack, definition(5, 7, false)),
+  );
+  let entity = h.spawn(&handle, Key::Attack);
+
+  assert_eq!(h.frames(entity, 2), vec![6, 7]);
+  assert!(h.complete(entity));
+  assert_eq!(h.finished_events(), vec![(entity, Key::Attack)]);
+
+  // stays on the last frame without sending the event again
+  assert_eq!(h.frames(entity, 3), vec![7, 7, 7]);
+  assert!(h.finished_events().is_empty());
+}
+
+#[test]
+fn changing_the_request_restarts_playback() {
+  let set = AnimationSet::new()
+    .with(Key::Run, definition(0, 3, true))
+    .with(Key::Attack, definition(4, 5, false));
+  let (mut h, handle) = Harness::new(0, set);
+  let entity = h.spawn(&handle, Key::Attack);
+
+  assert_eq!(h.frames(entity, 1), vec![5]);
+  assert!(h.complete(entity));
+
+  h.app
+    .world
+    .get_mut::<RequestedAnimation<Key>>(entity)
+    .unwrap()
+    .play = Key::Run;
+  h.settle();
+  assert_eq!(h.frame(entity), 0);
+  assert!(!h.complete(entity));
+  assert_eq!(h.frames(entity, 2), vec![1, 2]);
+}
+
+#[test]
+fn random_start_is_reproducible_with_the_same_seed() {
+  let def = AnimationDefinition {
+    random_start: true,
+    ..definition(0, 15, true)
+  };
+  let start_frames = |seed| {
+    let (mut h, handle) = Harness::new(seed, AnimationSet::new().with(Key::Run, def.clone()));
+    let frames = (0..8)
+      .map(|_| {
+        let entity = h.spawn(&handle, Key::Run);
+        h.frame(entity)
+      })
+      .collect::<Vec<_>>();
+    // the start frames are drawn from the seeded resource, not some other source
+    let drawn = h.app.world.resource_mut::<GameRng>().next_u64();
+    assert_ne!(drawn, GameRng::seeded(seed).next_u64());
+    frames
+  };
+
+  assert_eq!(start_frames(42), start_frames(42));
+  assert_ne!(start_frames(42), start_frames(43));
+  assert!(start_frames(42).iter().all(|frame| *frame <= 15));
+}
+
+#[test]
+fn random_start_completes_after_a_full_cycle() {
+  let def = AnimationDefinition {
+    random_start: true,
+    ..definition(0, 3, false)
+  };
+  let (mut h, handle) = Harness::new(7, AnimationSet::new().with(Key::Attack, def));
+  let entity = h.spawn(&handle, Key::Attack);
+  let start = h.frame(entity);
+
+  h.frames(entity, 3);
+  assert!(!h.complete(entity));
+  h.step();
+  assert_eq!(h.frame(entity), start);
+  assert!(h.complete(entity));
+}
+
+#[test]
+fn image_sequences_show_the_current_frame() {
+  let (mut h, handle) = Harness::new(
+    0,
+    AnimationSet::new().with(Key::Run, definition(0, 2, true)),
+  );
+  let frames: Vec<Handle<Image>> = (0..3)
+    .map(|_| Handle::weak(HandleId::random::<Image>()))
+    .collect();
+  let entity = h
+    .app
+    .world
+    .spawn()
+    .insert(RequestedAnimation::new(Key::Run))
+    .insert(handle)
+    .insert(ImageSequence::new(frames.clone()))
+    .insert(frames[0].clone())
+    .id();
+  h.settle();
+
+  let shown = |h: &Harness| h.app.world.get::<Handle<Image>>(entity).unwrap().clone();
+  for expected in [1, 2, 0] {
+    h.step();
+    assert_eq!(
+      h.app.world.get::<ImageSequence>(entity).unwrap().index,
+      expected
+    );
+    assert_eq!(shown(&h), frames[expected]);
+  }
+}
+
+fn event(frame: usize, name: &str) -> FrameEvent {
+  FrameEvent {
+    frame,
+    name: name.to_string(),
+  }
+}
+
+#[test]
+fn frame_events_fire_once_per_name_when_their_frame_shows() {
+  let def = AnimationDefinition {
+    events: vec![
+      event(0, "start"),
+      event(2, "step"),
+      event(2, "dust"),
+      event(2, "step"),
+    ],
+    ..definition(0, 3, true)
+  };
+  let (mut h, handle) = Harness::new(0, AnimationSet::new().with(Key::Run, def));
+  let entity = h.spawn(&handle, Key::Run);
+  let names = |events: Vec<(Entity, String)>| -> Vec<String> {
+    assert!(events.iter().all(|(e, _)| *e == entity));
+    events.into_iter().map(|(_, name)| name).collect()
+  };
+
+  // the first frame counts as shown when the animation starts
+  assert_eq!(names(h.frame_events()), vec!["start"]);
+  h.step();
+  assert!(h.frame_events().is_empty());
+  h.step();
+  assert_eq!(names(h.frame_events()), vec!["step", "dust"]);
+  h.step();
+  assert!(h.frame_events().is_empty());
+  h.step();
+  assert_eq!(names(h.frame_events()), vec!["start"]);
+}
+
+#[test]
+fn frames_passed_within_one_update_still_send_their_events() {
+  let def = AnimationDefinition {
+    events: vec![event(1, "step"), event(3, "step")],
+    ..definition(0, 3, true)
+  };
+  let (mut h, handle) = Harness::new(0, AnimationSet::new().with(Key::Run, def));
+  let playback = AnimationPlayback {
+    speed: 4.,
+    ..Default::default()
+  };
+  let entity = spawn_with(&mut h, &handle, Key::Run, playback);
+
+  h.step();
+  assert_eq!(h.frame(entity), 0);
+  assert_eq!(h.frame_events().len(), 2);
+}
+
+fn spawn_with(
+  h: &mut Harness,
+  handle: &Handle<AnimationSet<Key>>,
+  key: Key,
+  playback: AnimationPlayback,
+) -> Entity {
+  let entity = h
+    .app
+    .world
+    .spawn()
+    .insert(RequestedAnimation::new(key))
+    .insert(handle.clone())
+    .insert(playback)
+    .insert(TextureAtlasSprite::default())
+    .id();
+  h.settle();
+  entity
+}
+
+#[test]
+fn speed_can_advance_several_frames_per_update() {
+  let (mut h, handle) = Harness::new(
+    0,
+    AnimationSet::new().with(Key::Run, definition(0, 7, true)),
+  );
+  let playback = AnimationPlayback {
+    speed: 2.,
+    ..Default::default()
+  };
+  let entity = spawn_with(&mut h, &handle, Key::Run, playback);
+
+  assert_eq!(h.frames(entity, 5), vec![2, 4, 6, 0, 2]);
+}
+
+#[test]
+fn time_scale_applies_to_every_animation() {
+  let (mut h, handle) = Harness::new(
+    0,
+    AnimationSet::new().with(Key::Run, definition(0, 7, true)),
+  );
+  let entity = h.spawn(&handle, Key::Run);
+
+  h.app.world.resource_mut::<AnimationTimeScale>().0 = 0.;
+  assert_eq!(h.frames(entity, 3), vec![0, 0, 0]);
+  h.app.world.resource_mut::<AnimationTimeScale>().0 = 3.;
+  assert_eq!(h.frames(entity, 2), vec![3, 6]);
+}
+
+#[test]
+fn paused_animations_hold_their_frame() {
+  let (mut h, handle) = Harness::new(
+    0,
+    AnimationSet::new().with(Key::Run, definition(0, 7, true)),
+  );
+  let entity = spawn_with(&mut h, &handle, Key::Run, AnimationPlayback::default());
+
+  assert_eq!(h.frames(entity, 1), vec![1]);
+  let pause = |h: &mut Harness, paused| {
+    h.app
+      .world
+      .get_mut::<AnimationPlayback>(entity)
+      .unwrap()
+      .paused = paused;
+  };
+  pause(&mut h, true);
+  assert_eq!(h.frames(entity, 3), vec![1, 1, 1]);
+  pause(&mut h, false);
+  assert_eq!(h.frames(entity, 2), vec![2, 3]);
+}
+
+#[test]
+fn reversed_repeating_animation_wraps_to_its_end() {
+  let (mut h, handle) = Harness::new(
+    0,
+    AnimationSet::new().with(Key::Run, definition(2, 4, true)),
+  );
+  let playback = AnimationPlayback {
+    reverse: true,
+    ..Default::default()
+  };
+  let entity = spawn_with(&mut h, &handle, Key::Run, playback);
+
+  assert_eq!(h.frame(entity), 4);
+  assert_eq!(h.frames(entity, 5), vec![3, 2, 4, 3, 2]);
+  assert!(!h.complete(entity));
+}
+
+#[test]
+fn ping_pong_finishes_back_on_its_first_frame() {
+  let def = AnimationDefinition {
+    direction: AnimationDirection::PingPong,
+    ..definition(0, 2, false)
+  };
+  let (mut h, handle) = Harness::new(0, AnimationSet::new().with(Key::Attack, def));
+  let entity = h.spawn(&handle, Key::Attack);
+
+  // reaching the end only turns it around
+  assert_eq!(h.frames(entity, 3), vec![1, 2, 1]);
+  assert!(h.finished_events().is_empty());
+  assert_eq!(h.frames(entity, 1), vec![0]);
+  assert!(h.complete(entity));
+  assert_eq!(h.finished_events(), vec![(entity, Key::Attack)]);
+  assert_eq!(h.frames(entity, 2), vec![0, 0]);
+}
+
+fn boxes(hitboxes: &[f32], hurtboxes: &[f32]) -> FrameBoxes {
+  let rect = |x: &f32| FrameRect {
+    offset: (*x, 0.),
+    size: (4., 8.),
+  };
+  FrameBoxes {
+    hitboxes: hitboxes.iter().map(rect).collect(),
+    hurtboxes: hurtboxes.iter().map(rect).collect(),
+  }
+}
+
+// the sensor children with their kind and x offset
+fn colliders(h: &Harness, entity: Entity) -> Vec<(Entity, BoxKind, f32)> {
+  let children = match h.app.world.get::<Children>(entity) {
+    Some(children) => children.iter().copied().collect::<Vec<_>>(),
+    None => return Vec::new(),
+  };
+  children
+    .into_iter()
+    .filter_map(|child| {
+      let collider = h.app.world.get::<FrameCollider>(child)?;
+      let transform = h.app.world.get::<Transform>(child)?;
+      Some((child, collider.kind, transform.translation.x))
+    })
+    .collect()
+}
+
+#[test]
+fn frame_colliders_follow_the_frame() {
+  let mut def = definition(0, 2, true);
+  def.boxes.insert(0, boxes(&[10.], &[-2.]));
+  def.boxes.insert(1, boxes(&[20.], &[]));
+  let (mut h, handle) = Harness::new(0, AnimationSet::new().with(Key::Attack, def));
+  let entity = h
+    .app
+    .world
+    .spawn()
+    .insert(RequestedAnimation::new(Key::Attack))
+    .insert(handle)
+    .insert(HitboxLayers::player())
+    .insert(TextureAtlasSprite::default())
+    .id();
+  h.settle();
+
+  let shown = colliders(&h, entity);
+  let kinds: Vec<_> = shown.iter().map(|(_, kind, x)| (*kind, *x)).collect();
+  assert_eq!(kinds, vec![(BoxKind::Hit, 10.), (BoxKind::Hurt, -2.)]);
+  let hitbox = shown[0].0;
+  let child = h.app.world.entity(hitbox);
+  assert!(child.contains::<SensorShape>());
+  assert_eq!(
+    child.get::<CollisionLayers>(),
+    Some(&HitboxLayers::player().hit)
+  );
+
+  // the hitbox is moved rather than respawned, the hurtbox goes
+  h.step();
+  assert_eq!(colliders(&h, entity), vec![(hitbox, BoxKind::Hit, 20.)]);
+  assert!(h.app.world.get_entity(shown[1].0).is_none());
+
+  h.app
+    .world
+    .get_mut::<TextureAtlasSprite>(entity)
+    .unwrap()
+    .flip_x = true;
+  h.settle();
+  assert_eq!(colliders(&h, entity), vec![(hitbox, BoxKind::Hit, -20.)]);
+
+  h.step();
+  assert!(colliders(&h, entity).is_empty());
+  assert!(h.app.world.get_entity(hitbox).is_none());
+}
+
+fn spawn_layer(h: &mut Harness, parent: Entity, set: Option<Handle<AnimationSet<Key>>>) -> Entity {
+  let mut layer = h.app.world.spawn();
+  layer
+    .insert(AnimationLayer)
+    .insert(TextureAtlasSprite::default());
+  if let Some(set) = set {
+    layer.insert(set);
+  }
+  let layer = layer.id();
+  h.app.world.entity_mut(parent).push_children(&[layer]);
+  layer
+}
+
+#[test]
+fn layers_follow_their_parents_frame() {
+  let (mut h, handle) = Harness::new(
+    0,
+    AnimationSet::new().with(Key::Run, definition(0, 3, true)),
+  );
+  // the sword's atlas has its run frames elsewhere, and one fewer of them
+  let sword = h
+    .app
+    .world
+    .resource_mut::<Assets<AnimationSet<Key>>>()
+    .add(AnimationSet::new().with(Key::Run, definition(10, 12, true)));
+  let entity = h.spawn(&handle, Key::Run);
+  let mapped = spawn_layer(&mut h, entity, Some(sword));
+  let copied = spawn_layer(&mut h, entity, None);
+  h.settle();
+
+  let mut shown = Vec::new();
+  for _ in 0..4 {
+    h.step();
+    shown.push((h.frame(entity), h.frame(mapped), h.frame(copied)));
+  }
+  assert_eq!(shown, vec![(1, 11, 1), (2, 12, 2), (3, 12, 3), (0, 10, 0)]);
+
+  h.app
+    .world
+    .get_mut::<TextureAtlasSprite>(entity)
+    .unwrap()
+    .flip_x = true;
+  h.settle();
+  let flipped = |layer| h.app.world.get::<TextureAtlasSprite>(layer).unwrap().flip_x;
+  assert!(flipped(mapped) && flipped(copied));
+}
+
+fn turn(h: &mut Harness, entity: Entity, facing: Facing) {
+  h.app.world.entity_mut(entity).insert(facing);
+  h.settle();
+}
+
+#[test]
+fn turning_to_another_variant_keeps_the_animation_going() {
+  let mut attack = definition(0, 3, false);
+  attack
+    .variants
+    .insert(Facing::Up, definition(10, 13, false));
+  let (mut h, handle) = Harness::new(0, AnimationSet::new().with(Key::Attack, attack));
+  let entity = h.spawn(&handle, Key::Attack);
+
+  assert_eq!(h.frames(entity, 1), vec![1]);
+  turn(&mut h, entity, Facing::Up);
+  assert_eq!(h.frame(entity), 11);
+  assert_eq!(h.frames(entity, 1), vec![12]);
+  turn(&mut h, entity, Facing::Right);
+  assert_eq!(h.frame(entity), 2);
+
+  // finishes when it would have without turning
+  assert_eq!(h.frames(entity, 1), vec![3]);
+  assert_eq!(h.finished_events(), vec![(entity, Key::Attack)]);
+}
+
+#[test]
+fn turning_without_a_variant_change_leaves_playback_alone() {
+  let mut attack = definition(0, 3, false);
+  attack
+    .variants
+    .insert(Facing::Up, definition(10, 13, false));
+  let (mut h, handle) = Harness::new(0, AnimationSet::new().with(Key::Attack, attack));
+  let entity = h.spawn(&handle, Key::Attack);
+
+  assert_eq!(h.frames(entity, 1), vec![1]);
+  for facing in [Facing::Left, Facing::Right, Facing::Left] {
+    turn(&mut h, entity, facing);
+    assert_eq!(h.frame(entity), 1);
+  }
+  assert_eq!(h.frames(entity, 2), vec![2, 3]);
+  assert!(h.complete(entity));
+}
+
+fn machine_set() -> AnimationSet<Key> {
+  AnimationSet::new()
+    .with(Key::Idle, definition(0, 1, true))
+    .with(Key::Run, definition(2, 3, true))
+    .with(Key::Attack, definition(4, 5, false))
+    .with(Key::Hurt, definition(6, 7, false))
+}
+
+fn machine() -> AnimationStateMachine<Key> {
+  AnimationStateMachine::new(Key::Idle)
+    .with_state(Key::Attack, 1)
+    .with_state(Key::Hurt, 2)
+    .with_transition(Key::Idle, Key::Run, vec![Condition::greater("speed", 0.1)])
+    .with_transition(Key::Run, Key::Idle, vec![Condition::less("speed", 0.1)])
+    .with_transition(Key::Idle, Key::Attack, vec![Condition::is("attack", true)])
+    .with_transition(Key::Run, Key::Attack, vec![Condition::is("attack", true)])
+    .with_transition(
+      Key::Attack,
+      Key::Run,
+      vec![Condition::greater("speed", 0.1)],
+    )
+    .with_transition(Key::Attack, Key::Idle, vec![Condition::Finished])
+    .with_transition(Key::Hurt, Key::Idle, vec![Condition::Finished])
+    .with_any_transition(Key::Hurt, vec![Condition::is("hurt", true)])
+}
+
+fn spawn_machine(h: &mut Harness, handle: &Handle<AnimationSet<Key>>) -> Entity {
+  let entity = h
+    .app
+    .world
+    .spawn()
+    .insert(machine())
+    .insert(AnimationParams::default())
+    .insert(handle.clone())
+    .insert(TextureAtlasSprite::default())
+    .id();
+  // the first update only inserts the request
+  h.settle();
+  h.settle();
+  entity
+}
+
+#[test]
+fn state_machine_requests_its_initial_state() {
+  let (mut h, handle) = Harness::new(0, machine_set());
+  let entity = spawn_machine(&mut h, &handle);
+
+  assert_eq!(h.requested(entity), Key::Idle);
+  assert_eq!(h.frame(entity), 0);
+}
+
+#[test]
+fn state_machine_follows_its_params() {
+  let (mut h, handle) = Harness::new(0, machine_set());
+  let entity = spawn_machine(&mut h, &handle);
+
+  h.params(entity).set_float("speed", 1.);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Run);
+  assert_eq!(h.frame(entity), 2);
+
+  h.params(entity).set_float("speed", 0.);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Idle);
+
+  // missing params never satisfy a condition
+  h.params(entity).set_bool("speed", true);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Idle);
+}
+
+#[test]
+fn lower_priority_states_wait_for_the_animation_to_finish() {
+  let (mut h, handle) = Harness::new(0, machine_set());
+  let entity = spawn_machine(&mut h, &handle);
+
+  h.params(entity).set_bool("attack", true);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Attack);
+
+  h.params(entity).set_bool("attack", false);
+  h.params(entity).set_float("speed", 1.);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Attack);
+
+  assert_eq!(h.frames(entity, 1), vec![5]);
+  assert!(h.complete(entity));
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Run);
+}
+
+#[test]
+fn higher_priority_states_interrupt_right_away() {
+  let (mut h, handle) = Harness::new(0, machine_set());
+  let entity = spawn_machine(&mut h, &handle);
+
+  h.params(entity).set_bool("attack", true);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Attack);
+
+  h.params(entity).set_bool("hurt", true);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Hurt);
+  assert_eq!(h.frame(entity), 6);
+
+  // any-state transitions don't restart their own target
+  h.params(entity).set_bool("attack", false);
+  assert_eq!(h.frames(entity, 1), vec![7]);
+  h.params(entity).set_bool("hurt", false);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Idle);
+}
+
+fn spawn_queued(h: &mut Harness, handle: &Handle<AnimationSet<Key>>) -> Entity {
+  let entity = h.spawn(handle, Key::Idle);
+  h.app
+    .world
+    .entity_mut(entity)
+    .insert(AnimationQueue::<Key>::default());
+  entity
+}
+
+#[test]
+fn queue_plays_in_order_then_returns_to_the_base() {
+  let (mut h, handle) = Harness::new(0, machine_set());
+  let entity = spawn_queued(&mut h, &handle);
+
+  h.queue(entity).enqueue(Key::Attack);
+  h.queue(entity).enqueue(Key::Hurt);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Attack);
+  assert_eq!(h.frame(entity), 4);
+
+  // the next entry waits for the current one to complete
+  assert_eq!(h.frames(entity, 1), vec![5]);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Hurt);
+  assert_eq!(h.frame(entity), 6);
+
+  assert_eq!(h.frames(entity, 1), vec![7]);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Idle);
+  assert!(!h.queue(entity).is_playing());
+}
+
+#[test]
+fn play_once_overrides_the_queue() {
+  let (mut h, handle) = Harness::new(0, machine_set());
+  let entity = spawn_queued(&mut h, &handle);
+
+  h.queue(entity).enqueue(Key::Attack);
+  h.queue(entity).enqueue(Key::Attack);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Attack);
+
+  h.queue(entity).play_once(Key::Hurt);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Hurt);
+  assert_eq!(h.frame(entity), 6);
+
+  // the dropped entries don't come back, the queue returns to what played before it
+  assert_eq!(h.frames(entity, 1), vec![7]);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Idle);
+  assert!(!h.queue(entity).is_playing());
+}
+
+#[test]
+fn queue_skips_repeating_and_missing_animations() {
+  let set = AnimationSet::new()
+    .with(Key::Idle, definition(0, 1, true))
+    .with(Key::Run, definition(2, 3, true))
+    .with(Key::Attack, definition(4, 5, false));
+  let (mut h, handle) = Harness::new(0, set);
+  let entity = spawn_queued(&mut h, &handle);
+
+  h.queue(entity).enqueue(Key::Run);
+  h.queue(entity).enqueue(Key::Hurt);
+  h.queue(entity).enqueue(Key::Attack);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Attack);
+  assert_eq!(h.frames(entity, 1), vec![5]);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Idle);
+
+  // nothing playable, the base keeps playing undisturbed
+  assert_eq!(h.frames(entity, 1), vec![1]);
+  h.queue(entity).enqueue(Key::Run);
+  h.settle();
+  assert_eq!(h.requested(entity), Key::Idle);
+  assert_eq!(h.frame(entity), 1);
+  assert!(!h.queue(entity).is_playing());
+}
@@ -0,0 +1,90 @@
+use bevy::reflect::TypeUuid;
+use game_animation::*;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, TypeUuid)]
+#[uuid = "9e4d2b61-7a3c-4f85-b0d2-6c1e8f3a5b47"]
+enum Key {
+  Idle,
+  Attack,
+}
+
+fn definition(start: usize, end: usize) -> AnimationDefinition {
+  AnimationDefinition {
+    start,
+    end,
+    fps: 10.,
+    repeat: true,
+    ..Default::default()
+  }
+}
+
+#[test]
+fn valid_definition_passes() {
+  assert_eq!(definition(0, 3).validate(Some(4)), Ok(()));
+  assert_eq!(definition(2, 2).validate(None), Ok(()));
+}
+
+#[test]
+fn end_before_start_is_rejected() {
+  assert_eq!(
+    definition(3, 1).validate(None),
+    Err(AnimationError::StartAfterEnd { start: 3, end: 1 })
+  );
+}
+
+#[test]
+fn repeat_from_must_be_within_the_range() {
+  let def = AnimationDefinition {
+    repeat_from: Some(5),
+    ..definition(0, 3)
+  };
+  assert_eq!(
+    def.validate(None),
+    Err(AnimationError::RepeatFromOutOfRange { repeat_from: 5 })
+  );
+}
+
+#[test]
+fn frames_must_exist_in_the_atlas() {
+  assert_eq!(
+    definition(0, 4).validate(Some(4)),
+    Err(AnimationError::FrameOutsideAtlas {
+      frame: 4,
+      atlas_len: 4
+    })
+  );
+}
+
+#[test]
+fn fps_is_optional_with_complete_frame_durations() {
+  let def = AnimationDefinition {
+    fps: 0.,
+    frame_durations: Some(vec![0.1, 0.2]),
+    ..definition(0, 1)
+  };
+  assert_eq!(def.validate(None), Ok(()));
+
+  let missing = AnimationDefinition {
+    frame_durations: Some(vec![0.1]),
+    ..def
+  };
+  assert_eq!(missing.validate(None), Err(AnimationError::InvalidFps(0.)));
+}
+
+#[test]
+fn set_reports_every_invalid_key() {
+  let set = AnimationSet::new()
+    .with(Key::Idle, definition(0, 3))
+    .with(Key::Attack, definition(6, 4));
+
+  let err = set.validate(Some(8)).unwrap_err();
+  assert_eq!(
+    err.0,
+    vec![(
+      "Attack".to_string(),
+      AnimationError::StartAfterEnd { start: 6, end: 4 }
+    )]
+  );
+  assert!(err.to_string().contains("Attack"));
+}
@@ -0,0 +1,223 @@
+use crate::SoundController;
+use bevy::{
+  asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+  prelude::*,
+  reflect::TypeUuid,
+  utils::BoxedFuture,
+};
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
+use std::{
+  collections::{HashMap, VecDeque},
+  hash::Hash,
+};
+
+/// How a cue picks its next clip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Selection {
+  Random,
+  // never one of the last n clips played, as far as the cue has enough clips
+  AvoidLast(usize),
+  // every clip once, in random order, before any repeats
+  Shuffle,
+}
+
+impl Default for Selection {
+  fn default() -> Self {
+    Self::AvoidLast(1)
+  }
+}
+
+fn full_volume() -> f32 {
+  1.
+}
+
+/// A logical sound like "footstep" played from one of several clips. Jitter is the most volume
+/// or playback speed moves away from its base in either direction, speed also changes the pitch.
+/// `max_voices` limits how many of the cue play at once, see `VoiceStealing`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SoundCue {
+  pub clips: Vec<String>,
+  #[serde(default = "full_volume")]
+  pub volume: f32,
+  #[serde(default)]
+  pub volume_jitter: f32,
+  #[serde(default)]
+  pub pitch_jitter: f32,
+  #[serde(default)]
+  pub selection: Selection,
+  #[serde(default)]
+  pub priority: u32,
+  #[serde(default)]
+  pub max_voices: Option<usize>,
+  #[serde(skip)]
+  pub handles: Vec<Handle<AudioSource>>,
+}
+
+impl SoundCue {
+  pub fn validate(&self) -> Result<(), anyhow::Error> {
+    if self.clips.is_empty() {
+      return Err(anyhow::anyhow!("no clips"));
+    }
+    // a jitter of 1 could stop the clip or play it backwards
+    let in_range = |jitter: f32| (0. ..1.).contains(&jitter);
+    if !in_range(self.volume_jitter) || !in_range(self.pitch_jitter) {
+      return Err(anyhow::anyhow!("jitter must be at least 0 and below 1"));
+    }
+    Ok(())
+  }
+}
+
+/// Cues by name, loaded from `bank.ron` files. Register banks with
+/// `SoundController::add_bank` to play their cues.
+#[derive(Clone, Debug, Deserialize, TypeUuid)]
+#[uuid = "c4f1a8e2-6b3d-4e97-8a25-1d9e7c3b5f60"]
+pub struct SoundBank {
+  pub cues: HashMap<String, SoundCue>,
+}
+
+#[derive(Default)]
+pub struct SoundBankLoader;
+
+impl AssetLoader for SoundBankLoader {
+  fn load<'a>(
+    &'a self,
+    bytes: &'a [u8],
+    load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+    Box::pin(async move {
+      let mut bank: SoundBank = ron::de::from_bytes(bytes)?;
+      let mut dependencies = Vec::new();
+      for (name, cue) in bank.cues.iter_mut() {
+        cue
+          .validate()
+          .map_err(|err| anyhow::anyhow!("cue `{}`: {}", name, err))?;
+        for clip in cue.clips.iter() {
+          let path = AssetPath::from(clip.as_str()).to_owned();
+          cue.handles.push(load_context.get_handle(path.clone()));
+          dependencies.push(path);
+        }
+      }
+      load_context.set_default_asset(LoadedAsset::new(bank).with_dependencies(dependencies));
+      Ok(())
+    })
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &["bank.ron"]
+  }
+}
+
+/// What was picked for one play of a cue.
+pub(crate) struct CueSound {
+  pub clip: Handle<AudioSource>,
+  pub volume: f32,
+  pub speed: f32,
+  pub priority: u32,
+  pub max_voices: Option<usize>,
+}
+
+fn jitter(rng: &mut impl Rng, amount: f32) -> f32 {
+  if amount > 0. {
+    1. + rng.gen_range(-amount..amount)
+  } else {
+    1.
+  }
+}
+
+#[derive(Default)]
+struct CuePicks {
+  // of the cue the picks were made for
+  clips: usize,
+  recent: VecDeque<usize>,
+  bag: Vec<usize>,
+}
+
+/// Remembers recent picks and shuffle bags per cue name.
+#[derive(Default)]
+pub struct CueHistory {
+  cues: HashMap<String, CuePicks>,
+}
+
+impl CueHistory {
+  /// The index of the clip to play next for the cue, following its `selection`.
+  pub fn pick(&mut self, name: &str, cue: &SoundCue, rng: &mut impl Rng) -> usize {
+    let count = cue.clips.len();
+    let picks = self.cues.entry(name.to_string()).or_default();
+    // another bank overriding the cue or a reload can change its clips, older picks don't apply
+    if picks.clips != count {
+      *picks = CuePicks {
+        clips: count,
+        ..Default::default()
+      };
+    }
+    let CuePicks { recent, bag, .. } = picks;
+    let pick = match cue.selection {
+      Selection::Random => rng.gen_range(0..count),
+      Selection::AvoidLast(n) => {
+        let avoid = n.min(count - 1);
+        let candidates: Vec<_> = (0..count)
+          .filter(|clip| !recent.iter().rev().take(avoid).any(|r| r == clip))
+          .collect();
+        *candidates.choose(rng).expect("at least one clip is never avoided")
+      }
+      Selection::Shuffle => {
+        if bag.is_empty() {
+          bag.extend(0..count);
+          bag.shuffle(rng);
+          // don't let the new round start with the clip that ended the last one
+          if count > 1 && recent.back() == bag.last() {
+            bag.swap(0, count - 1);
+          }
+        }
+        bag.pop().expect("the bag was just refilled")
+      }
+    };
+
+    recent.push_back(pick);
+    if recent.len() > count {
+      recent.pop_front();
+    }
+    pick
+  }
+}
+
+impl<L> SoundController<L>
+where
+  L: Eq + Hash,
+{
+  /// Makes the bank's cues playable, later banks override cues with the same name.
+  pub fn add_bank(&mut self, bank: Handle<SoundBank>) {
+    self.banks.push(bank);
+  }
+
+  pub(crate) fn resolve_cue(
+    &mut self,
+    banks: &Assets<SoundBank>,
+    rng: &mut impl Rng,
+    name: &str,
+  ) -> Option<CueSound> {
+    let cue = self
+      .banks
+      .iter()
+      .rev()
+      .filter_map(|handle| banks.get(handle))
+      .find_map(|bank| bank.cues.get(name));
+    let cue = match cue {
+      Some(cue) => cue,
+      None => {
+        warn!("unknown sound cue `{}`", name);
+        return None;
+      }
+    };
+
+    let pick = self.cue_history.pick(name, cue, rng);
+    Some(CueSound {
+      clip: cue.handles[pick].clone(),
+      volume: cue.volume * jitter(rng, cue.volume_jitter),
+      speed: jitter(rng, cue.pitch_jitter),
+      priority: cue.priority,
+      max_voices: cue.max_voices,
+    })
+  }
+}
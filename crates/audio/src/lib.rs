@@ -1,59 +1,444 @@
-use std::collections::HashMap;
+use std::{
+  collections::{HashMap, HashSet},
+  fmt::Debug,
+  hash::Hash,
+  marker::PhantomData,
+};
 
-use bevy::{audio::AudioSink, prelude::*};
+use bevy::{
+  audio::{play_queued_audio_system, AudioOutput, AudioSink},
+  prelude::*,
+};
+use game_utils::GameRng;
 
+mod bank;
+mod sound;
+mod spatial;
+mod voice;
+pub use bank::*;
+pub use sound::*;
+pub use spatial::*;
+pub use voice::*;
+
+/// Audio requests, `L` names the layers a track can be played in. Playing in a layer replaces
+/// whatever the layer was playing. Volumes are scaled by the layer and master volumes.
 #[derive(Clone, Debug)]
-pub enum AudioCommand {
+pub enum AudioCommand<L> {
+  // one-shots played from a path count against the voice limit like cues
   Play(String),
-  PlayInLayer(String, String),
-  StopLayer(String),
+  PlayInLayer(String, L),
+  // stops the layer's track and every sound played in the layer, looping spatial sounds included
+  StopLayer(L),
+  // a one-shot that leaves the layer's track alone but follows its volume
+  PlayWithVolume(String, Option<L>, f32),
+  // `TrackSettings::default().with_volume(..)` plays the track at another volume
+  PlayInLayerWith(String, L, TrackSettings),
+  // stops the layer's track once it has faded out over the given seconds
+  FadeOutLayer(L, f32),
+  // attenuated and panned by where the source is relative to the listener while it plays, loops
+  // stop with their entity or their layer
+  PlaySpatial(String, Option<L>, SoundPosition, SpatialSettings),
+  // stops looping spatial sounds attached to the entity
+  StopEntitySounds(Entity),
+  // a one-shot picked from a cue of the registered sound banks, limited by the cue's voices
+  PlayCue(String, Option<L>),
+  PlaySpatialCue(String, Option<L>, SoundPosition, SpatialSettings),
+}
+
+/// How a track starts and how the track it replaces stops. Fading the new track in while the old
+/// one fades out crossfades them.
+#[derive(Clone, Copy, Debug)]
+pub struct TrackSettings {
+  pub volume: f32,
+  pub repeat: bool,
+  // seconds, 0 starts at full volume
+  pub fade_in: f32,
+  // seconds, 0 stops the replaced track right away
+  pub fade_out: f32,
+}
+
+impl Default for TrackSettings {
+  fn default() -> Self {
+    Self {
+      volume: 1.,
+      repeat: false,
+      fade_in: 0.,
+      fade_out: 0.,
+    }
+  }
+}
+
+impl TrackSettings {
+  pub fn crossfade(seconds: f32) -> Self {
+    Self {
+      fade_in: seconds,
+      fade_out: seconds,
+      ..Default::default()
+    }
+  }
+
+  pub fn looping(mut self) -> Self {
+    self.repeat = true;
+    self
+  }
+
+  pub fn with_volume(mut self, volume: f32) -> Self {
+    self.volume = volume;
+    self
+  }
+}
+
+/// How far a track has faded in, from 0 to 1. Fades take the given seconds to go all the way,
+/// so a fade out that starts halfway through fading in takes half as long.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fade {
+  level: f32,
+  // change of `level` per second, negative when fading out
+  rate: f32,
+}
+
+impl Fade {
+  /// Starts silent, or at full volume when `seconds` is 0.
+  pub fn fade_in(seconds: f32) -> Self {
+    if seconds > 0. {
+      Self {
+        level: 0.,
+        rate: 1. / seconds,
+      }
+    } else {
+      Self { level: 1., rate: 0. }
+    }
+  }
+
+  /// Continues from the current level, 0 seconds silences it right away.
+  pub fn fade_out(&mut self, seconds: f32) {
+    if seconds > 0. {
+      self.rate = -1. / seconds;
+    } else {
+      self.level = 0.;
+      self.rate = 0.;
+    }
+  }
+
+  pub fn step(&mut self, seconds: f32) {
+    self.level = (self.level + self.rate * seconds).clamp(0., 1.);
+  }
+
+  pub fn level(&self) -> f32 {
+    self.level
+  }
+
+  pub fn is_silent(&self) -> bool {
+    self.level <= 0.
+  }
+}
+
+/// Whether a track's fade moves this frame. Fades wait for their track to start playing, and a
+/// replaced track keeps its level until the track replacing it has started, so a slow load
+/// doesn't use up a crossfade.
+pub fn fade_moves(started: bool, replacement_started: Option<bool>) -> bool {
+  started && replacement_started.unwrap_or(true)
+}
+
+struct Track {
+  sink: Handle<AudioSink>,
+  volume: f32,
+  fade: Fade,
+}
+
+impl Track {
+  fn gain(&self) -> f32 {
+    self.volume * self.fade.level()
+  }
+}
+
+/// Everything playing follows volume changes, one-shots included.
+pub struct SoundController<L> {
+  layers: HashMap<L, Track>,
+  // replaced or stopped tracks still fading out
+  fading_out: Vec<(L, Track)>,
+  // one-shots and spatial sounds
+  sounds: Vec<Sound<L>>,
+  banks: Vec<Handle<SoundBank>>,
+  cue_history: CueHistory,
+  voices: Voices,
+  volumes: HashMap<L, f32>,
+  muted: HashSet<L>,
+  master_volume: f32,
+  master_muted: bool,
+}
+
+impl<L> Default for SoundController<L> {
+  fn default() -> Self {
+    Self {
+      layers: HashMap::new(),
+      fading_out: Vec::new(),
+      sounds: Vec::new(),
+      banks: Vec::new(),
+      cue_history: CueHistory::default(),
+      voices: Voices::default(),
+      volumes: HashMap::new(),
+      muted: HashSet::new(),
+      master_volume: 1.,
+      master_muted: false,
+    }
+  }
+}
+
+impl<L> SoundController<L>
+where
+  L: Eq + Hash,
+{
+  pub fn master_volume(&self) -> f32 {
+    self.master_volume
+  }
+
+  pub fn set_master_volume(&mut self, volume: f32) {
+    self.master_volume = volume.max(0.);
+  }
+
+  pub fn is_master_muted(&self) -> bool {
+    self.master_muted
+  }
+
+  pub fn set_master_muted(&mut self, muted: bool) {
+    self.master_muted = muted;
+  }
+
+  pub fn layer_volume(&self, layer: &L) -> f32 {
+    self.volumes.get(layer).copied().unwrap_or(1.)
+  }
+
+  pub fn set_layer_volume(&mut self, layer: L, volume: f32) {
+    self.volumes.insert(layer, volume.max(0.));
+  }
+
+  pub fn is_layer_muted(&self, layer: &L) -> bool {
+    self.muted.contains(layer)
+  }
+
+  pub fn set_layer_muted(&mut self, layer: L, muted: bool) {
+    if muted {
+      self.muted.insert(layer);
+    } else {
+      self.muted.remove(&layer);
+    }
+  }
+
+  /// What a sound played at full volume in `layer` is scaled by.
+  pub fn gain(&self, layer: Option<&L>) -> f32 {
+    if self.master_muted || layer.is_some_and(|l| self.is_layer_muted(l)) {
+      return 0.;
+    }
+    self.master_volume * layer.map_or(1., |l| self.layer_volume(l))
+  }
 }
 
-pub struct SoundController {
-  layers: HashMap<String, Handle<AudioSink>>,
+pub struct AudioPlugin<L> {
+  phantom: PhantomData<L>,
 }
 
-pub struct AudioPlugin;
-impl Plugin for AudioPlugin {
+impl<L> Default for AudioPlugin<L> {
+  fn default() -> Self {
+    Self {
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<L> Plugin for AudioPlugin<L>
+where
+  L: Send + Sync + Eq + Hash + Clone + Debug + 'static,
+{
   fn build(&self, app: &mut App) {
     app
-      .add_event::<AudioCommand>()
-      .insert_resource(SoundController {
-        layers: HashMap::new(),
-      })
-      .add_system(play_audio);
+      .add_event::<AudioCommand<L>>()
+      .init_resource::<SoundController<L>>()
+      .init_resource::<GameRng>()
+      .add_asset::<SoundBank>()
+      .init_asset_loader::<SoundBankLoader>()
+      // one-shots and spatial sounds play through their own output to apply their controls
+      .add_asset::<ControlledSound>()
+      .init_resource::<Audio<ControlledSound>>()
+      .init_non_send_resource::<AudioOutput<ControlledSound>>()
+      .add_system_to_stage(
+        CoreStage::PostUpdate,
+        play_queued_audio_system::<ControlledSound>,
+      )
+      .add_system(play_audio::<L>)
+      // after the commands, so stopped sounds are forgotten the frame they stop
+      .add_system(
+        update_tracks::<L>
+          .after(play_audio::<L>)
+          .after(spatial::play_spatial_audio::<L>),
+      )
+      .add_system(spatial::play_spatial_audio::<L>)
+      .add_system(spatial::update_spatial_audio::<L>)
+      .add_system(sound::start_sounds::<L>);
   }
 }
 
-fn play_audio(
-  mut cmds: EventReader<AudioCommand>,
-  mut controller: ResMut<SoundController>,
+#[allow(clippy::too_many_arguments)]
+fn play_audio<L>(
+  mut cmds: EventReader<AudioCommand<L>>,
+  mut controller: ResMut<SoundController<L>>,
   asset_server: Res<AssetServer>,
   audio: Res<Audio>,
   audio_sinks: Res<Assets<AudioSink>>,
-) {
+  banks: Res<Assets<SoundBank>>,
+  mut rng: ResMut<GameRng>,
+  time: Res<Time>,
+) where
+  L: Send + Sync + Eq + Hash + Clone + 'static,
+{
+  let now = time.seconds_since_startup();
   for cmd in cmds.iter() {
     match cmd {
       AudioCommand::Play(path) => {
-        let src = asset_server.load(path);
-        audio.play(src);
+        let sound = Sound::new(asset_server.load(path.as_str()), None, 1., 1.);
+        controller.play_voice(path, sound, 0, None, now);
+      }
+      AudioCommand::PlayWithVolume(path, layer, volume) => {
+        let sound = Sound::new(asset_server.load(path.as_str()), layer.clone(), *volume, 1.);
+        controller.play_voice(path, sound, 0, None, now);
+      }
+      AudioCommand::PlayCue(name, layer) => {
+        let cue = match controller.resolve_cue(&banks, &mut *rng, name) {
+          Some(cue) => cue,
+          None => continue,
+        };
+        let sound = Sound::new(cue.clip, layer.clone(), cue.volume, cue.speed);
+        controller.play_voice(name, sound, cue.priority, cue.max_voices, now);
       }
       AudioCommand::PlayInLayer(path, layer) => {
-        let src = asset_server.load(path);
-        let handle = audio_sinks.get_handle(audio.play(src));
-        if let Some(prev) = controller.layers.insert(layer.clone(), handle) {
-          if let Some(sink) = audio_sinks.get(&prev) {
-            sink.stop();
-          }
-        }
+        let settings = TrackSettings::default();
+        let track =
+          play_track(&controller, &asset_server, &audio, &audio_sinks, path, layer, settings);
+        replace_track(&mut controller, &audio_sinks, layer, track, settings.fade_out);
+      }
+      AudioCommand::PlayInLayerWith(path, layer, settings) => {
+        let track =
+          play_track(&controller, &asset_server, &audio, &audio_sinks, path, layer, *settings);
+        replace_track(&mut controller, &audio_sinks, layer, track, settings.fade_out);
       }
       AudioCommand::StopLayer(layer) => {
-        if let Some(prev) = controller.layers.get(layer) {
-          if let Some(sink) = audio_sinks.get(&prev) {
-            sink.stop();
-          }
+        if let Some(prev) = controller.layers.remove(layer) {
+          fade_out(&mut controller, &audio_sinks, layer, prev, 0.);
         }
+        controller.stop_sounds(layer);
       }
+      AudioCommand::FadeOutLayer(layer, seconds) => {
+        if let Some(prev) = controller.layers.remove(layer) {
+          fade_out(&mut controller, &audio_sinks, layer, prev, *seconds);
+        }
+      }
+      AudioCommand::PlaySpatial(..)
+      | AudioCommand::PlaySpatialCue(..)
+      | AudioCommand::StopEntitySounds(_) => {}
     }
   }
 }
+
+fn play_track<L: Eq + Hash>(
+  controller: &SoundController<L>,
+  asset_server: &AssetServer,
+  audio: &Audio,
+  audio_sinks: &Assets<AudioSink>,
+  path: &str,
+  layer: &L,
+  settings: TrackSettings,
+) -> Track {
+  let src = asset_server.load(path);
+  let fade = Fade::fade_in(settings.fade_in);
+  let playback = if settings.repeat {
+    PlaybackSettings::LOOP
+  } else {
+    PlaybackSettings::ONCE
+  };
+  let playback =
+    playback.with_volume(settings.volume * fade.level() * controller.gain(Some(layer)));
+  Track {
+    sink: audio_sinks.get_handle(audio.play_with_settings(src, playback)),
+    volume: settings.volume,
+    fade,
+  }
+}
+
+fn replace_track<L: Eq + Hash + Clone>(
+  controller: &mut SoundController<L>,
+  audio_sinks: &Assets<AudioSink>,
+  layer: &L,
+  track: Track,
+  fade_out_seconds: f32,
+) {
+  if let Some(prev) = controller.layers.insert(layer.clone(), track) {
+    fade_out(controller, audio_sinks, layer, prev, fade_out_seconds);
+  }
+}
+
+// A fade out continues from wherever the track's fade is, even halfway through fading in.
+fn fade_out<L: Clone>(
+  controller: &mut SoundController<L>,
+  audio_sinks: &Assets<AudioSink>,
+  layer: &L,
+  mut track: Track,
+  seconds: f32,
+) {
+  if seconds > 0. {
+    track.fade.fade_out(seconds);
+    controller.fading_out.push((layer.clone(), track));
+  } else if let Some(sink) = audio_sinks.get(&track.sink) {
+    sink.stop();
+  }
+}
+
+// Sinks only exist once the audio output has picked up the track, so this keeps checking rather
+// than waiting for the controller to change. Other sounds take their volume from their controls.
+fn update_tracks<L>(
+  time: Res<Time>,
+  mut controller: ResMut<SoundController<L>>,
+  audio_sinks: Res<Assets<AudioSink>>,
+) where
+  L: Send + Sync + Eq + Hash + 'static,
+{
+  let dt = time.delta_seconds();
+  let controller = &mut *controller;
+  let started = |track: &Track| audio_sinks.get(&track.sink).is_some();
+  for (layer, track) in controller.fading_out.iter_mut() {
+    let replacement_started = controller.layers.get(layer).map(started);
+    if fade_moves(started(track), replacement_started) {
+      track.fade.step(dt);
+    }
+  }
+  for track in controller.layers.values_mut() {
+    if fade_moves(started(track), None) {
+      track.fade.step(dt);
+    }
+  }
+
+  let set_volume = |layer: &L, track: &Track| {
+    if let Some(sink) = audio_sinks.get(&track.sink) {
+      let volume = track.gain() * controller.gain(Some(layer));
+      if (sink.volume() - volume).abs() > f32::EPSILON {
+        sink.set_volume(volume);
+      }
+    }
+  };
+  for (layer, track) in controller.layers.iter() {
+    set_volume(layer, track);
+  }
+  for (layer, track) in controller.fading_out.iter() {
+    set_volume(layer, track);
+  }
+
+  // a track that hasn't started yet can only be stopped once it has
+  controller.fading_out.retain(|(_, track)| match audio_sinks.get(&track.sink) {
+    Some(sink) if track.fade.is_silent() => {
+      sink.stop();
+      false
+    }
+    _ => true,
+  });
+
+  controller.update_sounds();
+}
@@ -0,0 +1,322 @@
+use crate::{SoundController, SoundPosition, SpatialSettings};
+use bevy::{
+  asset::LoadState,
+  audio::Decodable,
+  prelude::*,
+  reflect::TypeUuid,
+};
+use rodio::{source::Buffered, Sample, Source};
+use std::{
+  hash::Hash,
+  io::Cursor,
+  sync::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    Arc,
+  },
+  time::Duration,
+};
+
+/// Shared with a playing sound, changes apply to the samples it plays next. They also apply
+/// before the audio output has picked the sound up, unlike changes to an `AudioSink`.
+pub struct SoundControls {
+  // bits of an f32
+  volume: AtomicU32,
+  // bits of an f32, -1 is fully left and 1 fully right
+  pan: AtomicU32,
+  stopped: AtomicBool,
+  finished: AtomicBool,
+}
+
+impl SoundControls {
+  pub fn new(volume: f32) -> Self {
+    Self {
+      volume: AtomicU32::new(volume.to_bits()),
+      pan: AtomicU32::new(0f32.to_bits()),
+      stopped: AtomicBool::new(false),
+      finished: AtomicBool::new(false),
+    }
+  }
+
+  pub fn volume(&self) -> f32 {
+    f32::from_bits(self.volume.load(Ordering::Relaxed))
+  }
+
+  pub fn set_volume(&self, volume: f32) {
+    self.volume.store(volume.to_bits(), Ordering::Relaxed);
+  }
+
+  pub fn pan(&self) -> f32 {
+    f32::from_bits(self.pan.load(Ordering::Relaxed))
+  }
+
+  pub fn set_pan(&self, pan: f32) {
+    self.pan.store(pan.clamp(-1., 1.).to_bits(), Ordering::Relaxed);
+  }
+
+  pub fn stop(&self) {
+    self.stopped.store(true, Ordering::Relaxed);
+  }
+
+  pub fn is_stopped(&self) -> bool {
+    self.stopped.load(Ordering::Relaxed)
+  }
+
+  /// The sound has played to its end, or stopped playing after it was stopped.
+  pub fn is_finished(&self) -> bool {
+    self.finished.load(Ordering::Relaxed)
+  }
+
+  pub(crate) fn is_done(&self) -> bool {
+    self.is_stopped() || self.is_finished()
+  }
+}
+
+// Balance rather than constant power, so centered sounds keep their full volume.
+fn pan_gains(pan: f32) -> (f32, f32) {
+  ((1. - pan).min(1.), (1. + pan).min(1.))
+}
+
+/// Plays `source` following its [`SoundControls`]. Looping happens here rather than in the sink
+/// so the controls keep applying to every pass. Mono sources play in stereo to be panned, sources
+/// with more than two channels aren't panned.
+pub struct ControlledSource<S>
+where
+  S: Source,
+  S::Item: Sample,
+{
+  source: Buffered<S>,
+  // a copy from the start, to play again when looping
+  restart: Option<Buffered<S>>,
+  controls: Arc<SoundControls>,
+  // of the next stereo sample
+  right: bool,
+  // right half of an upmixed mono sample
+  held: Option<S::Item>,
+}
+
+impl<S> ControlledSource<S>
+where
+  S: Source,
+  S::Item: Sample,
+{
+  pub fn new(source: S, controls: Arc<SoundControls>, repeat: bool) -> Self {
+    let source = source.buffered();
+    Self {
+      restart: repeat.then(|| source.clone()),
+      source,
+      controls,
+      right: false,
+      held: None,
+    }
+  }
+}
+
+impl<S> Iterator for ControlledSource<S>
+where
+  S: Source,
+  S::Item: Sample,
+{
+  type Item = S::Item;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if let Some(sample) = self.held.take() {
+      return Some(sample);
+    }
+    // read before taking the sample, an ended source reports a single channel
+    let mut channels = self.source.channels();
+    let sample = if self.controls.is_stopped() {
+      None
+    } else {
+      match (self.source.next(), &self.restart) {
+        (None, Some(restart)) => {
+          self.source = restart.clone();
+          self.right = false;
+          channels = self.source.channels();
+          self.source.next()
+        }
+        (sample, _) => sample,
+      }
+    };
+    let sample = match sample {
+      Some(sample) => sample.amplify(self.controls.volume()),
+      None => {
+        self.controls.finished.store(true, Ordering::Relaxed);
+        return None;
+      }
+    };
+
+    let (left, right) = pan_gains(self.controls.pan());
+    match channels {
+      1 => {
+        self.held = Some(sample.amplify(right));
+        Some(sample.amplify(left))
+      }
+      2 => {
+        let gain = if self.right { right } else { left };
+        self.right = !self.right;
+        Some(sample.amplify(gain))
+      }
+      _ => Some(sample),
+    }
+  }
+}
+
+impl<S> Source for ControlledSource<S>
+where
+  S: Source,
+  S::Item: Sample,
+{
+  fn current_frame_len(&self) -> Option<usize> {
+    let held = self.held.is_some() as usize;
+    match self.source.channels() {
+      1 => self.source.current_frame_len().map(|len| len * 2 + held),
+      _ => self.source.current_frame_len(),
+    }
+  }
+
+  fn channels(&self) -> u16 {
+    match self.source.channels() {
+      1 => 2,
+      channels => channels,
+    }
+  }
+
+  fn sample_rate(&self) -> u32 {
+    self.source.sample_rate()
+  }
+
+  fn total_duration(&self) -> Option<Duration> {
+    match self.restart {
+      Some(_) => None,
+      None => self.source.total_duration(),
+    }
+  }
+}
+
+// A clip together with the controls of one play of it. Only lives until the audio output has
+// picked it up.
+#[derive(TypeUuid)]
+#[uuid = "6d2a9f47-1c8e-4b35-a0f3-7e5b2c9d8a14"]
+pub(crate) struct ControlledSound {
+  clip: AudioSource,
+  controls: Arc<SoundControls>,
+  repeat: bool,
+}
+
+impl Decodable for ControlledSound {
+  type Decoder = ControlledSource<rodio::Decoder<Cursor<AudioSource>>>;
+  type DecoderItem = i16;
+
+  // like bevy's own sources, clips that can't be decoded are a bug in the game's assets
+  fn decoder(&self) -> Self::Decoder {
+    let decoder = rodio::Decoder::new(Cursor::new(self.clip.clone())).unwrap();
+    ControlledSource::new(decoder, self.controls.clone(), self.repeat)
+  }
+}
+
+/// A sound played outside of the layer tracks, kept to follow volume changes until it's done.
+pub(crate) struct Sound<L> {
+  pub controls: Arc<SoundControls>,
+  pub layer: Option<L>,
+  // before the layer and master volumes
+  pub volume: f32,
+  pub repeat: bool,
+  pub spatial: Option<(SoundPosition, SpatialSettings)>,
+  // updated while the source or the listener move, 1 for other sounds
+  pub attenuation: f32,
+  // taken once the clip has loaded and the sound was handed to the audio output
+  clip: Option<Handle<AudioSource>>,
+  speed: f32,
+}
+
+impl<L> Sound<L> {
+  pub fn new(clip: Handle<AudioSource>, layer: Option<L>, volume: f32, speed: f32) -> Self {
+    Self {
+      controls: Arc::new(SoundControls::new(0.)),
+      layer,
+      volume,
+      repeat: false,
+      spatial: None,
+      attenuation: 1.,
+      clip: Some(clip),
+      speed,
+    }
+  }
+
+  pub fn at(mut self, source: SoundPosition, settings: SpatialSettings, attenuation: f32) -> Self {
+    self.repeat = settings.repeat;
+    self.spatial = Some((source, settings));
+    self.attenuation = attenuation;
+    self
+  }
+}
+
+impl<L> SoundController<L>
+where
+  L: Eq + Hash,
+{
+  pub(crate) fn play(&mut self, sound: Sound<L>) -> Arc<SoundControls> {
+    let controls = sound.controls.clone();
+    controls.set_volume(sound.volume * sound.attenuation * self.gain(sound.layer.as_ref()));
+    self.sounds.push(sound);
+    controls
+  }
+
+  /// One-shots and spatial sounds that are playing or waiting for their clip to load.
+  pub fn sounds_playing(&self) -> usize {
+    self.sounds.len()
+  }
+
+  pub(crate) fn stop_sounds(&self, layer: &L) {
+    for sound in self.sounds.iter().filter(|s| s.layer.as_ref() == Some(layer)) {
+      sound.controls.stop();
+    }
+  }
+
+  // Applies volume changes and forgets sounds that are done.
+  pub(crate) fn update_sounds(&mut self) {
+    for sound in self.sounds.iter() {
+      let gain = self.gain(sound.layer.as_ref());
+      sound.controls.set_volume(sound.volume * sound.attenuation * gain);
+    }
+    let voices = &mut self.voices;
+    self.sounds.retain(|sound| {
+      let done = sound.controls.is_done();
+      if done {
+        voices.release(&sound.controls);
+      }
+      !done
+    });
+  }
+}
+
+// Sounds wait here for their clip like bevy's `Audio` does, but a clip that fails to load ends
+// the sound.
+pub(crate) fn start_sounds<L>(
+  mut controller: ResMut<SoundController<L>>,
+  mut sounds: ResMut<Assets<ControlledSound>>,
+  clips: Res<Assets<AudioSource>>,
+  asset_server: Res<AssetServer>,
+  audio: Res<Audio<ControlledSound>>,
+) where
+  L: Send + Sync + 'static,
+{
+  for sound in controller.sounds.iter_mut() {
+    let handle = match &sound.clip {
+      Some(handle) => handle,
+      None => continue,
+    };
+    if let Some(clip) = clips.get(handle) {
+      let source = sounds.add(ControlledSound {
+        clip: clip.clone(),
+        controls: sound.controls.clone(),
+        repeat: sound.repeat,
+      });
+      audio.play_with_settings(source, PlaybackSettings::ONCE.with_speed(sound.speed));
+      sound.clip = None;
+    } else if asset_server.get_load_state(handle) == LoadState::Failed {
+      sound.controls.stop();
+      sound.clip = None;
+    }
+  }
+}
@@ -0,0 +1,197 @@
+use crate::{AudioCommand, Sound, SoundBank, SoundController};
+use bevy::prelude::*;
+use game_utils::GameRng;
+use std::hash::Hash;
+
+/// Where a spatial sound comes from. Sounds attached to an entity follow it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SoundPosition {
+  Entity(Entity),
+  World(Vec2),
+}
+
+/// Hears spatial sounds, the active camera does when there is none.
+#[derive(Component, Default)]
+pub struct AudioListener;
+
+/// Sounds are at full volume up to `near` pixels from the listener and fade out linearly until
+/// `far`, where they are silent. They pan with their horizontal offset from the listener and play
+/// from one side only once it reaches `pan_width` pixels, 0 keeps them centered.
+#[derive(Clone, Copy, Debug)]
+pub struct SpatialSettings {
+  pub volume: f32,
+  pub repeat: bool,
+  pub near: f32,
+  pub far: f32,
+  pub pan_width: f32,
+}
+
+impl Default for SpatialSettings {
+  fn default() -> Self {
+    Self {
+      volume: 1.,
+      repeat: false,
+      near: 64.,
+      far: 640.,
+      pan_width: 320.,
+    }
+  }
+}
+
+impl SpatialSettings {
+  pub fn attenuation(&self, distance: f32) -> f32 {
+    if distance <= self.near {
+      1.
+    } else if distance >= self.far {
+      0.
+    } else {
+      1. - (distance - self.near) / (self.far - self.near)
+    }
+  }
+
+  /// From -1 when fully left to 1 when fully right, `offset` is the source's x minus the
+  /// listener's.
+  pub fn pan(&self, offset: f32) -> f32 {
+    if self.pan_width > 0. {
+      (offset / self.pan_width).clamp(-1., 1.)
+    } else {
+      0.
+    }
+  }
+}
+
+fn listener_position(
+  listeners: &Query<&GlobalTransform, With<AudioListener>>,
+  cameras: &Query<(&GlobalTransform, &Camera)>,
+) -> Option<Vec2> {
+  listeners
+    .iter()
+    .next()
+    .or_else(|| {
+      cameras
+        .iter()
+        .find(|(_, camera)| camera.is_active)
+        .map(|(transform, _)| transform)
+    })
+    .map(|transform| transform.translation().truncate())
+}
+
+fn source_position(source: SoundPosition, transforms: &Query<&GlobalTransform>) -> Option<Vec2> {
+  match source {
+    SoundPosition::Entity(entity) => transforms
+      .get(entity)
+      .ok()
+      .map(|transform| transform.translation().truncate()),
+    SoundPosition::World(position) => Some(position),
+  }
+}
+
+// The attenuation and pan. Without a listener there is nothing to measure from, so sounds play
+// unattenuated and centered.
+fn placement(
+  settings: &SpatialSettings,
+  source: Option<Vec2>,
+  listener: Option<Vec2>,
+) -> (f32, f32) {
+  match (source, listener) {
+    (Some(source), Some(listener)) => (
+      settings.attenuation(source.distance(listener)),
+      settings.pan(source.x - listener.x),
+    ),
+    _ => (1., 0.),
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn play_spatial_audio<L>(
+  mut cmds: EventReader<AudioCommand<L>>,
+  mut controller: ResMut<SoundController<L>>,
+  asset_server: Res<AssetServer>,
+  listeners: Query<&GlobalTransform, With<AudioListener>>,
+  cameras: Query<(&GlobalTransform, &Camera)>,
+  transforms: Query<&GlobalTransform>,
+  banks: Res<Assets<SoundBank>>,
+  mut rng: ResMut<GameRng>,
+  time: Res<Time>,
+) where
+  L: Send + Sync + Eq + Hash + Clone + 'static,
+{
+  let listener = listener_position(&listeners, &cameras);
+  let now = time.seconds_since_startup();
+  for cmd in cmds.iter() {
+    let (src, layer, source, settings, speed, voice) = match cmd {
+      AudioCommand::PlaySpatial(path, layer, source, settings) => {
+        // looping sounds from a path aren't one-shots, the voice limits leave them alone
+        let voice = (!settings.repeat).then_some((path, 0, None));
+        (asset_server.load(path.as_str()), layer, source, *settings, 1., voice)
+      }
+      AudioCommand::PlaySpatialCue(name, layer, source, settings) => {
+        let cue = match controller.resolve_cue(&banks, &mut *rng, name) {
+          Some(cue) => cue,
+          None => continue,
+        };
+        let settings = SpatialSettings {
+          volume: settings.volume * cue.volume,
+          ..*settings
+        };
+        let voice = Some((name, cue.priority, cue.max_voices));
+        (cue.clip, layer, source, settings, cue.speed, voice)
+      }
+      AudioCommand::StopEntitySounds(entity) => {
+        for sound in controller.sounds.iter() {
+          let attached = matches!(
+            sound.spatial,
+            Some((SoundPosition::Entity(e), _)) if e == *entity
+          );
+          if attached && sound.repeat {
+            sound.controls.stop();
+          }
+        }
+        continue;
+      }
+      _ => continue,
+    };
+
+    let (attenuation, pan) =
+      placement(&settings, source_position(*source, &transforms), listener);
+    let sound =
+      Sound::new(src, layer.clone(), settings.volume, speed).at(*source, settings, attenuation);
+    sound.controls.set_pan(pan);
+    match voice {
+      Some((name, priority, max_for_cue)) => {
+        controller.play_voice(name, sound, priority, max_for_cue, now)
+      }
+      None => {
+        controller.play(sound);
+      }
+    }
+  }
+}
+
+// Looping sounds attached to a despawned entity stop with it, one-shots keep playing from where
+// it was last.
+pub(crate) fn update_spatial_audio<L>(
+  mut controller: ResMut<SoundController<L>>,
+  listeners: Query<&GlobalTransform, With<AudioListener>>,
+  cameras: Query<(&GlobalTransform, &Camera)>,
+  transforms: Query<&GlobalTransform>,
+) where
+  L: Send + Sync + 'static,
+{
+  let listener = listener_position(&listeners, &cameras);
+  for sound in controller.sounds.iter_mut() {
+    let (source, settings) = match sound.spatial {
+      Some(spatial) => spatial,
+      None => continue,
+    };
+    match source_position(source, &transforms) {
+      Some(position) => {
+        let (attenuation, pan) = placement(&settings, Some(position), listener);
+        sound.attenuation = attenuation;
+        sound.controls.set_pan(pan);
+      }
+      None if sound.repeat => sound.controls.stop(),
+      None => {}
+    }
+  }
+}
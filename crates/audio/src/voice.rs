@@ -0,0 +1,122 @@
+use crate::{Sound, SoundController, SoundControls};
+use std::{cmp::Ordering, hash::Hash, sync::Arc};
+
+/// Which voice gives way when a limit is reached. Only voices with the same or a lower priority
+/// than the new one can be stopped, otherwise the new one isn't played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VoiceStealing {
+  #[default]
+  Oldest,
+  Quietest,
+}
+
+/// A one-shot or cue sound counted against the voice limits. One-shots played from a path count
+/// as a cue named after the path.
+pub struct Voice {
+  pub controls: Arc<SoundControls>,
+  pub cue: String,
+  pub priority: u32,
+  pub started: f64,
+}
+
+/// Voices that are still playing, a voice ends once its sound is done.
+#[derive(Default)]
+pub struct Voices {
+  playing: Vec<Voice>,
+  pub max: Option<usize>,
+  pub stealing: VoiceStealing,
+}
+
+impl Voices {
+  /// Stops voices to make room for a new one of `cue`, false when it shouldn't play.
+  pub fn make_room(&mut self, cue: &str, max_for_cue: Option<usize>, priority: u32) -> bool {
+    self.playing.retain(|voice| !voice.controls.is_done());
+    if let Some(max) = max_for_cue {
+      if !self.steal(|voice| voice.cue == cue, max, priority) {
+        return false;
+      }
+    }
+    match self.max {
+      Some(max) => self.steal(|_| true, max, priority),
+      None => true,
+    }
+  }
+
+  pub fn add(&mut self, voice: Voice) {
+    self.playing.push(voice);
+  }
+
+  pub fn release(&mut self, controls: &Arc<SoundControls>) {
+    self.playing.retain(|voice| !Arc::ptr_eq(&voice.controls, controls));
+  }
+
+  fn steal(&mut self, counts: impl Fn(&Voice) -> bool, max: usize, priority: u32) -> bool {
+    let count = self.playing.iter().filter(|voice| counts(voice)).count();
+    if count < max {
+      return true;
+    }
+    let needed = count + 1 - max;
+
+    let mut candidates: Vec<_> = self
+      .playing
+      .iter()
+      .enumerate()
+      .filter(|(_, voice)| counts(voice) && voice.priority <= priority)
+      .collect();
+    if candidates.len() < needed {
+      return false;
+    }
+    // lowest priority first, then by the stealing rule
+    candidates.sort_by(|(_, a), (_, b)| {
+      a.priority.cmp(&b.priority).then_with(|| match self.stealing {
+        VoiceStealing::Oldest => a.started.partial_cmp(&b.started).unwrap_or(Ordering::Equal),
+        // as loud as they are now, spatial voices change as their source moves
+        VoiceStealing::Quietest => {
+          let (a, b) = (a.controls.volume(), b.controls.volume());
+          a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+      })
+    });
+    let mut victims: Vec<_> = candidates.iter().take(needed).map(|(i, _)| *i).collect();
+    victims.sort_unstable_by(|a, b| b.cmp(a));
+    for i in victims {
+      self.playing.remove(i).controls.stop();
+    }
+    true
+  }
+}
+
+impl<L> SoundController<L>
+where
+  L: Eq + Hash,
+{
+  /// Limits how many voices play at once, cues can also set their own limit.
+  pub fn set_max_voices(&mut self, max: Option<usize>) {
+    self.voices.max = max;
+  }
+
+  pub fn set_voice_stealing(&mut self, stealing: VoiceStealing) {
+    self.voices.stealing = stealing;
+  }
+
+  // Plays the sound as a voice of `cue` unless the limits leave no room for it.
+  pub(crate) fn play_voice(
+    &mut self,
+    cue: &str,
+    sound: Sound<L>,
+    priority: u32,
+    max_for_cue: Option<usize>,
+    now: f64,
+  ) {
+    if !self.voices.make_room(cue, max_for_cue, priority) {
+      return;
+    }
+    let controls = self.play(sound);
+    self.voices.add(Voice {
+      controls,
+      cue: cue.to_string(),
+      priority,
+      started: now,
+    });
+  }
+}
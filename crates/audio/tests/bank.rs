@@ -0,0 +1,169 @@
+use game_audio::*;
+use game_utils::GameRng;
+use rodio::Source;
+use std::{collections::HashMap, io::Cursor};
+
+fn cue(clips: usize, selection: &str) -> SoundCue {
+  let clips: Vec<_> = (0..clips).map(|i| format!("\"{}.ogg\"", i)).collect();
+  ron::from_str(&format!(
+    "(clips: [{}], selection: {})",
+    clips.join(", "),
+    selection
+  ))
+  .unwrap()
+}
+
+fn sequence(cue: &SoundCue, seed: u64, count: usize) -> Vec<usize> {
+  let mut history = CueHistory::default();
+  let mut rng = GameRng::seeded(seed);
+  (0..count)
+    .map(|_| history.pick("cue", cue, &mut rng))
+    .collect()
+}
+
+fn counts(picks: &[usize]) -> HashMap<usize, usize> {
+  let mut counts = HashMap::new();
+  for pick in picks {
+    *counts.entry(*pick).or_default() += 1;
+  }
+  counts
+}
+
+#[test]
+fn picks_are_reproducible_with_the_same_seed() {
+  for selection in ["Random", "AvoidLast(1)", "Shuffle"] {
+    let cue = cue(4, selection);
+    assert_eq!(
+      sequence(&cue, 7, 50),
+      sequence(&cue, 7, 50),
+      "{}",
+      selection
+    );
+    assert_ne!(
+      sequence(&cue, 7, 50),
+      sequence(&cue, 8, 50),
+      "{}",
+      selection
+    );
+  }
+}
+
+#[test]
+fn random_picks_spread_evenly() {
+  let picks = sequence(&cue(4, "Random"), 1, 4000);
+  let counts = counts(&picks);
+  assert_eq!(counts.len(), 4);
+  for (clip, count) in counts {
+    assert!(
+      (800..1200).contains(&count),
+      "clip {} picked {} times",
+      clip,
+      count
+    );
+  }
+}
+
+#[test]
+fn avoid_last_never_repeats_recent_clips() {
+  for seed in 0..10 {
+    let picks = sequence(&cue(3, "AvoidLast(1)"), seed, 300);
+    assert!(picks.windows(2).all(|w| w[0] != w[1]), "seed {}", seed);
+    assert_eq!(counts(&picks).len(), 3);
+
+    let picks = sequence(&cue(4, "AvoidLast(2)"), seed, 300);
+    assert!(
+      picks.windows(3).all(|w| w[2] != w[0] && w[2] != w[1]),
+      "seed {}",
+      seed
+    );
+  }
+}
+
+#[test]
+fn avoid_last_keeps_one_clip_playable() {
+  // avoiding more clips than the cue has still leaves the least recent one
+  let picks = sequence(&cue(2, "AvoidLast(5)"), 3, 20);
+  assert!(picks.windows(2).all(|w| w[0] != w[1]));
+
+  assert_eq!(sequence(&cue(1, "AvoidLast(1)"), 3, 5), vec![0; 5]);
+}
+
+#[test]
+fn shuffle_plays_every_clip_once_per_round() {
+  for seed in 0..10 {
+    let picks = sequence(&cue(5, "Shuffle"), seed, 50);
+    for round in picks.chunks(5) {
+      let mut round = round.to_vec();
+      round.sort_unstable();
+      assert_eq!(round, vec![0, 1, 2, 3, 4], "seed {}", seed);
+    }
+    // nor does a new round start with the clip that ended the last one
+    assert!(picks.windows(2).all(|w| w[0] != w[1]), "seed {}", seed);
+  }
+}
+
+#[test]
+fn cues_keep_separate_histories() {
+  let cue = cue(2, "Shuffle");
+  let mut history = CueHistory::default();
+  let mut rng = GameRng::seeded(5);
+  let first = history.pick("a", &cue, &mut rng);
+  // a fresh bag for the other cue doesn't take from the first one's
+  history.pick("b", &cue, &mut rng);
+  assert_eq!(history.pick("a", &cue, &mut rng), 1 - first);
+}
+
+#[test]
+fn sample_bank_clips_decode() {
+  let assets = concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets/");
+  let bank = std::fs::read(format!("{}audio/player.bank.ron", assets)).unwrap();
+  let bank: SoundBank = ron::de::from_bytes(&bank).unwrap();
+  assert!(bank.cues.contains_key("footstep"));
+
+  for (name, cue) in bank.cues.iter() {
+    assert!(cue.clips.len() > 1, "cue `{}` has no variation", name);
+    for clip in cue.clips.iter() {
+      let bytes = std::fs::read(format!("{}{}", assets, clip)).unwrap();
+      let decoder = rodio::Decoder::new(Cursor::new(bytes)).unwrap();
+      assert_eq!(decoder.channels(), 1, "{}", clip);
+      let peak = decoder.map(i16::unsigned_abs).max().unwrap_or(0);
+      assert!(peak > 1000, "`{}` is silent", clip);
+    }
+  }
+}
+
+#[test]
+fn picks_follow_a_cue_that_lost_clips() {
+  let mut history = CueHistory::default();
+  let mut rng = GameRng::seeded(2);
+  for selection in ["Shuffle", "AvoidLast(3)"] {
+    let (full, reduced) = (cue(5, selection), cue(2, selection));
+    history.pick(selection, &full, &mut rng);
+    for _ in 0..20 {
+      assert!(
+        history.pick(selection, &reduced, &mut rng) < 2,
+        "{}",
+        selection
+      );
+    }
+  }
+}
+
+#[test]
+fn jitter_must_stay_below_one() {
+  let cue = |jitter: &str| -> SoundCue {
+    ron::from_str(&format!(r#"(clips: ["0.ogg"], {})"#, jitter)).unwrap()
+  };
+  assert!(cue("volume_jitter: 0.5, pitch_jitter: 0.99")
+    .validate()
+    .is_ok());
+  assert!(cue("pitch_jitter: 1.").validate().is_err());
+  assert!(cue("volume_jitter: 1.5").validate().is_err());
+  assert!(cue("pitch_jitter: -0.1").validate().is_err());
+}
+
+#[test]
+fn cues_need_clips() {
+  let cue: SoundCue = ron::from_str("(clips: [])").unwrap();
+  assert!(cue.validate().is_err());
+}
@@ -0,0 +1,76 @@
+use game_audio::*;
+use game_utils::STEP;
+
+fn levels(fade: &mut Fade, steps: usize) -> Vec<f32> {
+  (0..steps)
+    .map(|_| {
+      fade.step(STEP);
+      fade.level()
+    })
+    .collect()
+}
+
+#[test]
+fn fading_in_rises_to_full_volume_and_stays() {
+  let mut fade = Fade::fade_in(1.);
+  assert_eq!(fade.level(), 0.);
+  assert_eq!(levels(&mut fade, 5), vec![0.25, 0.5, 0.75, 1., 1.]);
+}
+
+#[test]
+fn instant_fades_start_at_full_volume() {
+  let mut fade = Fade::fade_in(0.);
+  assert_eq!(fade.level(), 1.);
+  // frames without any time passing leave it alone
+  fade.step(0.);
+  assert_eq!(levels(&mut fade, 2), vec![1., 1.]);
+}
+
+#[test]
+fn fading_out_continues_from_the_current_level() {
+  let mut fade = Fade::fade_in(1.);
+  assert_eq!(levels(&mut fade, 2), vec![0.25, 0.5]);
+
+  fade.fade_out(1.);
+  assert_eq!(levels(&mut fade, 1), vec![0.25]);
+  assert!(!fade.is_silent());
+  assert_eq!(levels(&mut fade, 2), vec![0., 0.]);
+  assert!(fade.is_silent());
+}
+
+#[test]
+fn fading_out_without_time_silences_right_away() {
+  let mut fade = Fade::fade_in(0.);
+  fade.fade_out(0.);
+  assert!(fade.is_silent());
+  assert_eq!(levels(&mut fade, 1), vec![0.]);
+}
+
+#[test]
+fn crossfades_wait_for_the_new_track_to_start() {
+  let mut old = Fade::fade_in(0.);
+  let mut new = Fade::fade_in(1.);
+  old.fade_out(1.);
+
+  // the new track is still loading
+  let mut frame = |new_started: bool| {
+    if fade_moves(true, Some(new_started)) {
+      old.step(STEP);
+    }
+    if fade_moves(new_started, None) {
+      new.step(STEP);
+    }
+    (old.level(), new.level())
+  };
+  assert_eq!(frame(false), (1., 0.));
+  assert_eq!(frame(false), (1., 0.));
+  assert_eq!(frame(true), (0.75, 0.25));
+  assert_eq!(frame(true), (0.5, 0.5));
+}
+
+#[test]
+fn stopped_layers_fade_once_their_track_has_started() {
+  assert!(!fade_moves(false, None));
+  assert!(fade_moves(true, None));
+  assert!(!fade_moves(false, Some(true)));
+}
@@ -0,0 +1,63 @@
+use bevy::{
+  asset::{AssetPlugin, AssetServerSettings},
+  prelude::*,
+};
+use game_audio::{AudioCommand, AudioPlugin, SoundController, SoundPosition, SpatialSettings};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Layer {
+  Ambience,
+  Effects,
+}
+
+fn app() -> App {
+  let mut app = App::new();
+  app
+    .insert_resource(AssetServerSettings {
+      asset_folder: concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets").to_string(),
+      ..Default::default()
+    })
+    .add_plugins(MinimalPlugins)
+    .add_plugin(AssetPlugin)
+    .add_plugin(bevy::audio::AudioPlugin)
+    .add_plugin(AudioPlugin::<Layer>::default());
+  app
+}
+
+fn send(app: &mut App, cmd: AudioCommand<Layer>) {
+  app.world.send_event(cmd);
+  app.update();
+}
+
+fn playing(app: &App) -> usize {
+  app
+    .world
+    .resource::<SoundController<Layer>>()
+    .sounds_playing()
+}
+
+fn waterfall(layer: Layer) -> AudioCommand<Layer> {
+  AudioCommand::PlaySpatial(
+    "audio/land_1.ogg".to_string(),
+    Some(layer),
+    SoundPosition::World(Vec2::new(200., 0.)),
+    SpatialSettings {
+      repeat: true,
+      ..Default::default()
+    },
+  )
+}
+
+#[test]
+fn stopping_a_layer_stops_its_looping_world_sounds() {
+  let mut app = app();
+  send(&mut app, waterfall(Layer::Ambience));
+  send(&mut app, waterfall(Layer::Effects));
+  app.update();
+  assert_eq!(playing(&app), 2);
+
+  send(&mut app, AudioCommand::StopLayer(Layer::Ambience));
+  assert_eq!(playing(&app), 1);
+  send(&mut app, AudioCommand::StopLayer(Layer::Effects));
+  assert_eq!(playing(&app), 0);
+}
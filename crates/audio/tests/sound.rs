@@ -0,0 +1,58 @@
+use game_audio::*;
+use rodio::buffer::SamplesBuffer;
+use std::sync::Arc;
+
+fn clip(samples: &[i16]) -> SamplesBuffer<i16> {
+  SamplesBuffer::new(1, 44100, samples.to_vec())
+}
+
+#[test]
+fn volume_changes_apply_while_playing() {
+  let controls = Arc::new(SoundControls::new(0.5));
+  let mut source = ControlledSource::new(clip(&[100, 100, 100, 100]), controls.clone(), false);
+
+  // mono clips play in stereo, each sample comes out once per side
+  assert_eq!(source.by_ref().take(2).collect::<Vec<_>>(), vec![50, 50]);
+  controls.set_volume(0.);
+  assert_eq!(source.by_ref().take(2).collect::<Vec<_>>(), vec![0, 0]);
+  controls.set_volume(1.);
+  assert_eq!(source.by_ref().take(2).collect::<Vec<_>>(), vec![100, 100]);
+}
+
+#[test]
+fn finishing_is_reported_once_the_clip_runs_out() {
+  let controls = Arc::new(SoundControls::new(1.));
+  let mut source = ControlledSource::new(clip(&[1, 2]), controls.clone(), false);
+
+  assert_eq!(
+    source.by_ref().take(4).collect::<Vec<_>>(),
+    vec![1, 1, 2, 2]
+  );
+  assert!(!controls.is_finished());
+  assert_eq!(source.next(), None);
+  assert!(controls.is_finished());
+}
+
+#[test]
+fn stopping_ends_the_sound_early() {
+  let controls = Arc::new(SoundControls::new(1.));
+  let mut source = ControlledSource::new(clip(&[1, 2, 3]), controls.clone(), true);
+
+  assert_eq!(source.by_ref().take(2).collect::<Vec<_>>(), vec![1, 1]);
+  controls.stop();
+  assert!(controls.is_stopped());
+  assert_eq!(source.next(), None);
+  assert!(controls.is_finished());
+}
+
+#[test]
+fn looping_sounds_start_over() {
+  let controls = Arc::new(SoundControls::new(1.));
+  let source = ControlledSource::new(clip(&[1, 2, 3]), controls.clone(), true);
+
+  assert_eq!(
+    source.take(8).collect::<Vec<_>>(),
+    vec![1, 1, 2, 2, 3, 3, 1, 1]
+  );
+  assert!(!controls.is_finished());
+}
@@ -0,0 +1,63 @@
+use game_audio::*;
+use rodio::{buffer::SamplesBuffer, Source};
+use std::sync::Arc;
+
+fn settings() -> SpatialSettings {
+  SpatialSettings {
+    near: 100.,
+    far: 500.,
+    pan_width: 200.,
+    ..Default::default()
+  }
+}
+
+#[test]
+fn attenuation_falls_off_linearly_between_near_and_far() {
+  let settings = settings();
+  let falloff: Vec<_> = [0., 100., 200., 300., 400., 500., 900.]
+    .into_iter()
+    .map(|distance| settings.attenuation(distance))
+    .collect();
+  assert_eq!(falloff, vec![1., 1., 0.75, 0.5, 0.25, 0., 0.]);
+}
+
+#[test]
+fn pan_follows_the_horizontal_offset() {
+  let settings = settings();
+  let pans: Vec<_> = [-400., -200., -50., 0., 100., 200., 400.]
+    .into_iter()
+    .map(|offset| settings.pan(offset))
+    .collect();
+  assert_eq!(pans, vec![-1., -1., -0.25, 0., 0.5, 1., 1.]);
+
+  let centered = SpatialSettings {
+    pan_width: 0.,
+    ..settings
+  };
+  assert_eq!(centered.pan(400.), 0.);
+}
+
+#[test]
+fn mono_sources_are_panned_in_stereo() {
+  let controls = Arc::new(SoundControls::new(1.));
+  let clip = SamplesBuffer::new(1, 44100, vec![100i16; 4]);
+  let mut source = ControlledSource::new(clip, controls.clone(), false);
+  assert_eq!(source.channels(), 2);
+
+  assert_eq!(source.by_ref().take(2).collect::<Vec<_>>(), vec![100, 100]);
+  controls.set_pan(0.5);
+  assert_eq!(source.by_ref().take(2).collect::<Vec<_>>(), vec![50, 100]);
+  controls.set_pan(-1.);
+  assert_eq!(source.collect::<Vec<_>>(), vec![100, 0, 100, 0]);
+}
+
+#[test]
+fn stereo_sources_scale_each_side() {
+  let controls = Arc::new(SoundControls::new(1.));
+  controls.set_pan(0.75);
+  let clip = SamplesBuffer::new(2, 44100, vec![100i16, 80, 100, 80]);
+  let source = ControlledSource::new(clip, controls, false);
+  assert_eq!(source.channels(), 2);
+
+  assert_eq!(source.collect::<Vec<_>>(), vec![25, 80, 25, 80]);
+}
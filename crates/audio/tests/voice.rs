@@ -0,0 +1,127 @@
+use game_audio::*;
+use std::sync::Arc;
+
+struct Playing {
+  voices: Voices,
+  controls: Vec<Arc<SoundControls>>,
+}
+
+impl Playing {
+  fn new(max: Option<usize>, stealing: VoiceStealing) -> Self {
+    let mut voices = Voices::default();
+    voices.max = max;
+    voices.stealing = stealing;
+    Self {
+      voices,
+      controls: Vec::new(),
+    }
+  }
+
+  // returns whether it got to play, voices start a second apart
+  fn play(&mut self, cue: &str, max_for_cue: Option<usize>, priority: u32, volume: f32) -> bool {
+    if !self.voices.make_room(cue, max_for_cue, priority) {
+      return false;
+    }
+    let controls = Arc::new(SoundControls::new(volume));
+    self.voices.add(Voice {
+      controls: controls.clone(),
+      cue: cue.to_string(),
+      priority,
+      started: self.controls.len() as f64,
+    });
+    self.controls.push(controls);
+    true
+  }
+
+  fn stopped(&self) -> Vec<usize> {
+    (0..self.controls.len())
+      .filter(|i| self.controls[*i].is_stopped())
+      .collect()
+  }
+}
+
+#[test]
+fn the_oldest_voice_gives_way() {
+  let mut playing = Playing::new(Some(2), VoiceStealing::Oldest);
+  assert!(playing.play("a", None, 0, 1.));
+  assert!(playing.play("b", None, 0, 0.2));
+  assert!(playing.stopped().is_empty());
+
+  assert!(playing.play("c", None, 0, 1.));
+  assert_eq!(playing.stopped(), vec![0]);
+  assert!(playing.play("d", None, 0, 1.));
+  assert_eq!(playing.stopped(), vec![0, 1]);
+}
+
+#[test]
+fn the_quietest_voice_gives_way() {
+  let mut playing = Playing::new(Some(2), VoiceStealing::Quietest);
+  assert!(playing.play("a", None, 0, 1.));
+  assert!(playing.play("b", None, 0, 0.2));
+
+  assert!(playing.play("c", None, 0, 0.5));
+  assert_eq!(playing.stopped(), vec![1]);
+  assert!(playing.play("d", None, 0, 1.));
+  assert_eq!(playing.stopped(), vec![1, 2]);
+}
+
+#[test]
+fn lower_priorities_give_way_first() {
+  let mut playing = Playing::new(Some(2), VoiceStealing::Oldest);
+  assert!(playing.play("music_sting", None, 2, 1.));
+  assert!(playing.play("footstep", None, 0, 1.));
+
+  // newer than the sting, but less important
+  assert!(playing.play("hit", None, 1, 1.));
+  assert_eq!(playing.stopped(), vec![1]);
+}
+
+#[test]
+fn voices_above_the_new_priority_are_kept() {
+  let mut playing = Playing::new(Some(2), VoiceStealing::Oldest);
+  assert!(playing.play("a", None, 2, 1.));
+  assert!(playing.play("b", None, 1, 1.));
+
+  assert!(!playing.play("c", None, 0, 1.));
+  assert!(playing.stopped().is_empty());
+  // an equal priority can take a place
+  assert!(playing.play("d", None, 1, 1.));
+  assert_eq!(playing.stopped(), vec![1]);
+}
+
+#[test]
+fn cue_limits_only_steal_from_the_same_cue() {
+  let mut playing = Playing::new(None, VoiceStealing::Oldest);
+  assert!(playing.play("footstep", Some(2), 0, 1.));
+  assert!(playing.play("land", None, 0, 1.));
+  assert!(playing.play("footstep", Some(2), 0, 1.));
+
+  assert!(playing.play("footstep", Some(2), 0, 1.));
+  assert_eq!(playing.stopped(), vec![0]);
+  assert!(playing.play("land", None, 0, 1.));
+  assert_eq!(playing.stopped(), vec![0]);
+}
+
+#[test]
+fn finished_voices_free_their_place() {
+  let mut playing = Playing::new(Some(1), VoiceStealing::Oldest);
+  assert!(playing.play("a", None, 1, 1.));
+  assert!(!playing.play("b", None, 0, 1.));
+
+  playing.controls[0].stop();
+  assert!(playing.play("b", None, 0, 1.));
+  assert!(!playing.controls[1].is_stopped());
+}
+
+#[test]
+fn the_quietest_voice_is_judged_by_its_current_volume() {
+  let mut playing = Playing::new(Some(2), VoiceStealing::Quietest);
+  assert!(playing.play("a", None, 0, 0.2));
+  assert!(playing.play("b", None, 0, 1.));
+  // the first source came closer, the second moved away
+  playing.controls[0].set_volume(0.9);
+  playing.controls[1].set_volume(0.1);
+
+  assert!(playing.play("c", None, 0, 0.5));
+  assert_eq!(playing.stopped(), vec![1]);
+}
@@ -1,4 +1,6 @@
+use heron::PhysicsLayer as HeronPhysicsLayer;
 
+#[derive(HeronPhysicsLayer, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PhysicsLayer {
   World,
   Player,
@@ -9,6 +11,7 @@ pub enum PhysicsLayer {
   EnemyDamageSend
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum AudioLayer {
   Music,
   Player,
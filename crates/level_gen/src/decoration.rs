@@ -0,0 +1,192 @@
+use crate::{GenFiniteLevel, LevelLayout, Tile, TILE_SIZE};
+use bevy::{
+  asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+  prelude::*,
+  reflect::TypeUuid,
+  utils::BoxedFuture,
+};
+use bevy_ecs_tilemap::prelude::*;
+use rand::{distributions::WeightedIndex, prelude::*, rngs::StdRng};
+use serde::Deserialize;
+
+// mixed into the level seed so decorations don't mirror terrain decisions
+const DECORATION_SEED: u64 = 0x6465_636f_7261_7465;
+const DECORATION_Z: f32 = 1.;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Placement {
+  Floor,
+  Ceiling,
+  Wall,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Decoration {
+  pub name: String,
+  pub tile: u32,
+  pub weight: f32,
+  pub placement: Placement,
+}
+
+#[derive(Clone, Debug, Deserialize, TypeUuid)]
+#[uuid = "5b0d1f3c-8f0e-4a43-9d55-3f6b8a1c2e71"]
+pub struct DecorationSet {
+  pub texture: String,
+  pub tile_size: (f32, f32),
+  // chance that a valid surface gets a decoration
+  pub density: f64,
+  pub decorations: Vec<Decoration>,
+  #[serde(skip)]
+  pub texture_handle: Handle<Image>,
+}
+
+#[derive(Component)]
+pub struct Decorated;
+
+#[derive(Component)]
+pub struct DecorationLayer;
+
+#[derive(Default)]
+pub struct DecorationSetLoader;
+
+impl AssetLoader for DecorationSetLoader {
+  fn load<'a>(
+    &'a self,
+    bytes: &'a [u8],
+    load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+    Box::pin(async move {
+      let mut set: DecorationSet = ron::de::from_bytes(bytes)?;
+      let texture_path = AssetPath::from(set.texture.as_str()).to_owned();
+      set.texture_handle = load_context.get_handle(texture_path.clone());
+      load_context.set_default_asset(LoadedAsset::new(set).with_dependency(texture_path));
+      Ok(())
+    })
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &["decor.ron"]
+  }
+}
+
+pub fn scatter(layout: &LevelLayout, set: &DecorationSet, seed: u64) -> Vec<(UVec2, u32)> {
+  let mut rng = StdRng::seed_from_u64(seed ^ DECORATION_SEED);
+  let pools: Vec<_> = [Placement::Floor, Placement::Ceiling, Placement::Wall]
+    .into_iter()
+    .map(|placement| {
+      let candidates: Vec<_> = set
+        .decorations
+        .iter()
+        .filter(|d| d.placement == placement && d.weight > 0.)
+        .collect();
+      let weights = WeightedIndex::new(candidates.iter().map(|d| d.weight)).ok();
+      (placement, candidates, weights)
+    })
+    .collect();
+
+  let mut placed = Vec::new();
+  for y in 0..layout.height {
+    for x in 0..layout.width {
+      let placement = match surface_at(layout, x, y) {
+        Some(placement) => placement,
+        None => continue,
+      };
+      if !rng.gen_bool(set.density.clamp(0., 1.)) {
+        continue;
+      }
+      if let Some((_, candidates, Some(weights))) = pools.iter().find(|(p, ..)| *p == placement) {
+        placed.push((UVec2::new(x, y), candidates[weights.sample(&mut rng)].tile));
+      }
+    }
+  }
+
+  placed
+}
+
+// Only plain solid tiles count as surfaces, so secret entrances aren't given away. Rewards keep
+// their tile to themselves.
+fn surface_at(layout: &LevelLayout, x: u32, y: u32) -> Option<Placement> {
+  if layout.get(x, y) != Tile::Empty || layout.rewards.iter().any(|r| r.tile == UVec2::new(x, y)) {
+    return None;
+  }
+  let solid = |x: Option<u32>, y: Option<u32>| match (x, y) {
+    (Some(x), Some(y)) => layout.get(x, y) == Tile::Solid,
+    _ => false,
+  };
+
+  if solid(Some(x), y.checked_sub(1)) {
+    Some(Placement::Floor)
+  } else if solid(Some(x), Some(y + 1)) {
+    Some(Placement::Ceiling)
+  } else if solid(x.checked_sub(1), Some(y)) || solid(Some(x + 1), Some(y)) {
+    Some(Placement::Wall)
+  } else {
+    None
+  }
+}
+
+pub(crate) fn decorate_levels(
+  mut commands: Commands,
+  qry: Query<(Entity, &GenFiniteLevel, &LevelLayout), Without<Decorated>>,
+  sets: Res<Assets<DecorationSet>>,
+) {
+  for (entity, gen_config, layout) in qry.iter() {
+    let set = match gen_config.decorations.as_ref() {
+      Some(handle) => match sets.get(handle) {
+        Some(set) => set,
+        None => continue,
+      },
+      None => {
+        commands.entity(entity).insert(Decorated);
+        continue;
+      }
+    };
+
+    let tilemap_size = TilemapSize {
+      x: layout.width,
+      y: layout.height,
+    };
+    let tilemap_entity = commands.spawn().id();
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+
+    for (pos, tile) in scatter(layout, set, gen_config.seed) {
+      let tile_pos = TilePos { x: pos.x, y: pos.y };
+      let tile_entity = commands
+        .spawn()
+        .insert_bundle(TileBundle {
+          position: tile_pos,
+          tilemap_id: TilemapId(tilemap_entity),
+          texture: TileTexture(tile),
+          ..Default::default()
+        })
+        .id();
+      commands.entity(tilemap_entity).add_child(tile_entity);
+      tile_storage.set(&tile_pos, Some(tile_entity));
+    }
+
+    commands
+      .entity(tilemap_entity)
+      .insert_bundle(TilemapBundle {
+        grid_size: TilemapGridSize {
+          x: TILE_SIZE,
+          y: TILE_SIZE,
+        },
+        size: tilemap_size,
+        storage: tile_storage,
+        texture: TilemapTexture(set.texture_handle.clone()),
+        tile_size: TilemapTileSize {
+          x: set.tile_size.0,
+          y: set.tile_size.1,
+        },
+        // tiles are centered on their grid position
+        transform: Transform::from_xyz(TILE_SIZE / 2., TILE_SIZE / 2., DECORATION_Z),
+        ..Default::default()
+      })
+      .insert(DecorationLayer);
+
+    commands
+      .entity(entity)
+      .add_child(tilemap_entity)
+      .insert(Decorated);
+  }
+}
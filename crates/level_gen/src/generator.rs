@@ -0,0 +1,299 @@
+use crate::GenFiniteLevel;
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// interior height of secret rooms, in blocks
+const ROOM_HEIGHT: u32 = 2;
+const MIN_RUN: u32 = 3;
+const MAX_RUN: u32 = 6;
+const SECRET_CHANCE: f64 = 0.35;
+const MAX_BRANCH_STEPS: u32 = 3;
+const BRANCH_ATTEMPTS: u32 = 16;
+const HEADROOM: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+  Empty,
+  Solid,
+  BreakableWall,
+  FakeFloor,
+}
+
+impl Tile {
+  pub fn has_collider(&self) -> bool {
+    matches!(self, Tile::Solid | Tile::BreakableWall)
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecretKind {
+  BreakableWall,
+  FakeFloor,
+}
+
+#[derive(Clone, Debug)]
+pub struct SecretRoom {
+  pub kind: SecretKind,
+  // inclusive bounds of the carved interior
+  pub min: UVec2,
+  pub max: UVec2,
+  pub entrance: Vec<UVec2>,
+}
+
+#[derive(Clone, Debug)]
+pub struct OptionalBranch {
+  pub anchor: UVec2,
+  pub platforms: Vec<UVec2>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RewardSource {
+  Branch,
+  SecretRoom,
+}
+
+#[derive(Clone, Debug)]
+pub struct RewardPlacement {
+  pub tile: UVec2,
+  pub source: RewardSource,
+}
+
+#[derive(Component, Clone, Debug, Default)]
+pub struct LevelMetrics {
+  pub critical_path_length: u32,
+  pub optional_branches: u32,
+  pub secret_rooms: u32,
+  pub rewards: u32,
+  // tiles only reachable by leaving the critical path
+  pub optional_area: u32,
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct LevelLayout {
+  pub width: u32,
+  pub height: u32,
+  pub tiles: Vec<Tile>,
+  // ground height of the critical path for each column
+  pub surface: Vec<u32>,
+  pub branches: Vec<OptionalBranch>,
+  pub secret_rooms: Vec<SecretRoom>,
+  pub rewards: Vec<RewardPlacement>,
+  pub metrics: LevelMetrics,
+}
+
+impl LevelLayout {
+  pub fn get(&self, x: u32, y: u32) -> Tile {
+    if x < self.width && y < self.height {
+      self.tiles[(y * self.width + x) as usize]
+    } else {
+      Tile::Empty
+    }
+  }
+
+  pub fn tile_center(&self, tile: UVec2, tile_size: f32) -> Vec2 {
+    (tile.as_vec2() + Vec2::splat(0.5)) * tile_size
+  }
+
+  fn set(&mut self, x: u32, y: u32, tile: Tile) {
+    self.tiles[(y * self.width + x) as usize] = tile;
+  }
+
+  fn is_clear(&self, x: u32, min_y: u32, max_y: u32) -> bool {
+    max_y < self.height && (min_y..=max_y).all(|y| self.get(x, y) == Tile::Empty)
+  }
+}
+
+pub fn generate(config: &GenFiniteLevel) -> LevelLayout {
+  let mut rng = StdRng::seed_from_u64(config.seed);
+  let jump = config.max_jump_height.max(1);
+  let min_height = config.min_height.max(1);
+  let max_height = config.max_height.max(min_height);
+  let width = rng
+    .gen_range(config.min_width.min(config.max_width)..=config.max_width)
+    .max(1);
+  let height = max_height + jump * MAX_BRANCH_STEPS + HEADROOM;
+
+  let (surface, secret_rooms) = walk_surface(&mut rng, config, width, jump, min_height, max_height);
+
+  let mut layout = LevelLayout {
+    width,
+    height,
+    tiles: vec![Tile::Empty; (width * height) as usize],
+    surface,
+    branches: Vec::new(),
+    secret_rooms: Vec::new(),
+    rewards: Vec::new(),
+    metrics: LevelMetrics::default(),
+  };
+
+  for x in 0..width {
+    for y in 0..layout.surface[x as usize] {
+      layout.set(x, y, Tile::Solid);
+    }
+  }
+
+  for room in secret_rooms {
+    carve_room(&mut layout, room);
+  }
+
+  for _ in 0..config.branches {
+    if let Some(branch) = place_branch(&mut rng, &mut layout, jump) {
+      layout.branches.push(branch);
+    }
+  }
+
+  layout.metrics = LevelMetrics {
+    critical_path_length: width,
+    optional_branches: layout.branches.len() as u32,
+    secret_rooms: layout.secret_rooms.len() as u32,
+    rewards: layout.rewards.len() as u32,
+    optional_area: layout
+      .branches
+      .iter()
+      .map(|b| b.platforms.len() as u32)
+      .chain(
+        layout
+          .secret_rooms
+          .iter()
+          .map(|r| (r.max.x - r.min.x + 1) * (r.max.y - r.min.y + 1)),
+      )
+      .sum(),
+  };
+
+  layout
+}
+
+// Walks the critical path from left to right. Heights only ever rise by `jump` between runs so the
+// path stays traversable, drops are unrestricted. Secret rooms are planned during the walk because
+// they need flat ground (and for breakable walls, a cliff) around them.
+fn walk_surface(
+  rng: &mut StdRng,
+  config: &GenFiniteLevel,
+  width: u32,
+  jump: u32,
+  min_height: u32,
+  max_height: u32,
+) -> (Vec<u32>, Vec<SecretRoom>) {
+  let mut surface = Vec::with_capacity(width as usize);
+  let mut rooms = Vec::new();
+  let mut h = rng.gen_range(min_height..=max_height);
+
+  while (surface.len() as u32) < width {
+    let x = surface.len() as u32;
+    let room_width = rng.gen_range(2..=4u32);
+    let fits = x > 0 && width - x > room_width + 2 + MIN_RUN && h >= ROOM_HEIGHT + 2;
+    let wants_secret =
+      (rooms.len() as u32) < config.secret_rooms && fits && rng.gen_bool(SECRET_CHANCE);
+
+    // falling into a fake floor room means climbing back out through the hole
+    let can_fake_floor = jump > ROOM_HEIGHT;
+    let can_breakable = h > min_height + ROOM_HEIGHT;
+
+    if wants_secret && (can_fake_floor || can_breakable) {
+      let kind = if can_fake_floor && (!can_breakable || rng.gen_bool(0.5)) {
+        SecretKind::FakeFloor
+      } else {
+        SecretKind::BreakableWall
+      };
+      let columns = room_width + 2;
+      surface.extend(std::iter::repeat_n(h, columns as usize));
+
+      match kind {
+        SecretKind::FakeFloor => {
+          rooms.push(SecretRoom {
+            kind,
+            min: UVec2::new(x + 1, h - 1 - ROOM_HEIGHT),
+            max: UVec2::new(x + room_width, h - 2),
+            entrance: vec![UVec2::new(x + 1 + room_width / 2, h - 1)],
+          });
+        }
+        SecretKind::BreakableWall => {
+          // the ground drops right after the room so its wall faces the path
+          let lower = h - ROOM_HEIGHT - 1;
+          let wall = x + columns - 1;
+          rooms.push(SecretRoom {
+            kind,
+            min: UVec2::new(x + 1, lower),
+            max: UVec2::new(wall - 1, lower + ROOM_HEIGHT - 1),
+            entrance: (lower..lower + ROOM_HEIGHT)
+              .map(|y| UVec2::new(wall, y))
+              .collect(),
+          });
+          h = lower;
+        }
+      }
+      continue;
+    }
+
+    let run = rng.gen_range(MIN_RUN..=MAX_RUN).min(width - x);
+    surface.extend(std::iter::repeat_n(h, run as usize));
+
+    let delta = rng.gen_range(-(jump as i64)..=jump as i64);
+    h = (h as i64 + delta).clamp(min_height as i64, max_height as i64) as u32;
+  }
+
+  (surface, rooms)
+}
+
+fn carve_room(layout: &mut LevelLayout, room: SecretRoom) {
+  for x in room.min.x..=room.max.x {
+    for y in room.min.y..=room.max.y {
+      layout.set(x, y, Tile::Empty);
+    }
+  }
+
+  let entrance_tile = match room.kind {
+    SecretKind::BreakableWall => Tile::BreakableWall,
+    SecretKind::FakeFloor => Tile::FakeFloor,
+  };
+  for tile in room.entrance.iter() {
+    layout.set(tile.x, tile.y, entrance_tile);
+  }
+
+  layout.rewards.push(RewardPlacement {
+    tile: UVec2::new((room.min.x + room.max.x) / 2, room.min.y),
+    source: RewardSource::SecretRoom,
+  });
+  layout.secret_rooms.push(room);
+}
+
+// Places a chain of floating ledges climbing away from the critical path with a reward at the
+// end. Every ledge is one full jump above where the player stands.
+fn place_branch(rng: &mut StdRng, layout: &mut LevelLayout, jump: u32) -> Option<OptionalBranch> {
+  for _ in 0..BRANCH_ATTEMPTS {
+    let anchor_x = rng.gen_range(0..layout.width);
+    let anchor = UVec2::new(anchor_x, layout.surface[anchor_x as usize]);
+    let steps = rng.gen_range(1..=MAX_BRANCH_STEPS);
+
+    let mut platforms = Vec::new();
+    let mut stand = anchor;
+    for _ in 0..steps {
+      let row = stand.y + jump - 1;
+      let length = rng.gen_range(3..=5u32);
+      if row < 2 || stand.x + length >= layout.width {
+        break;
+      }
+
+      let columns = stand.x + 1..=stand.x + length;
+      if !columns.clone().all(|x| layout.is_clear(x, row - 2, row + 2)) {
+        break;
+      }
+
+      for x in columns {
+        layout.set(x, row, Tile::Solid);
+        platforms.push(UVec2::new(x, row));
+      }
+      stand = UVec2::new(stand.x + length, row + 1);
+    }
+
+    if !platforms.is_empty() {
+      layout.rewards.push(RewardPlacement {
+        tile: stand,
+        source: RewardSource::Branch,
+      });
+      return Some(OptionalBranch { anchor, platforms });
+    }
+  }
+
+  None
+}
@@ -1,31 +1,57 @@
 use bevy::{
   prelude::*,
-  sprite::MaterialMesh2dBundle,
   tasks::{AsyncComputeTaskPool, Task},
 };
-use heron::prelude::*;
 use futures_lite::future;
+use heron::prelude::*;
+
+mod decoration;
+mod generator;
+pub use decoration::*;
+pub use generator::*;
 
-#[derive(Component, Default)]
+pub const TILE_SIZE: f32 = 32.;
+
+#[derive(Component, Clone, Default)]
 pub struct GenFiniteLevel {
+  pub seed: u64,
   pub max_jump_height: u32, // in blocks
   pub min_height: u32,
   pub max_height: u32,
   pub min_width: u32,
   pub max_width: u32,
+  pub branches: u32,     // max optional branches off the critical path
+  pub secret_rooms: u32, // max hidden rooms
+  pub decorations: Option<Handle<DecorationSet>>,
 }
 
 #[derive(Component)]
 pub struct FiniteLevel;
 
 #[derive(Component)]
-struct LevelPending(Task<Mesh>);
+pub struct BreakableWall;
+
+#[derive(Component)]
+pub struct FakeFloor;
+
+#[derive(Component)]
+pub struct LevelReward {
+  pub source: RewardSource,
+}
+
+#[derive(Component)]
+struct LevelPending(Task<LevelLayout>);
 
 pub struct LevelGeneratorPlugin;
 
 impl Plugin for LevelGeneratorPlugin {
   fn build(&self, app: &mut App) {
-    app.add_system(spawn_build_tasks).add_system(poll_tasks);
+    app
+      .add_asset::<DecorationSet>()
+      .init_asset_loader::<DecorationSetLoader>()
+      .add_system(spawn_build_tasks)
+      .add_system(poll_tasks)
+      .add_system(decoration::decorate_levels);
   }
 }
 
@@ -35,52 +61,91 @@ fn spawn_build_tasks(
 ) {
   for (entity, gen_config) in qry.iter() {
     let thread_pool = AsyncComputeTaskPool::get();
-    let w = gen_config.max_width as f32 * 32.;
-    let h = gen_config.min_height as f32 * 32.;
-    // let task = thread_pool.spawn(async move {
-    //   // TODO: generate level
-
-    //   Mesh::from(shape::Quad {
-    //     size: Vec2::new(w, h),
-    //     flip: false,
-    //   })
-    // });
-
-    // // Spawn new entity and add our new task as a component
-    // commands.entity(entity).insert(LevelPending(task));
-
-    // .insert(RigidBody::Static)
-
-    //     // Attach a collision shape
-    //     .insert(CollisionShape::Sphere { radius: 10.0 })
-
-    //     // Optionally add other useful components...
-    //     .insert(Velocity::from_linear(Vec3::X * 2.0))
-    //     .insert(Acceleration::from_linear(Vec3::X * 1.0))
-    //     .insert(PhysicMaterial { friction: 1.0, density: 10.0, ..Default::default() })
-    //     .insert(RotationConstraints::lock())
-    //     .insert(CollisionLayers::none().with_group(Layer::Player).with_mask(Layer::World));
+    let config = gen_config.clone();
+    let task = thread_pool.spawn(async move { generate(&config) });
+
+    commands.entity(entity).despawn_descendants();
+    commands
+      .entity(entity)
+      .remove::<FiniteLevel>()
+      .remove::<LevelLayout>()
+      .remove::<Decorated>()
+      .insert(LevelPending(task));
   }
 }
 
-fn poll_tasks(
-  mut commands: Commands,
-  mut transform_tasks: Query<(Entity, &mut LevelPending)>,
-  mut meshes: ResMut<Assets<Mesh>>,
-  mut materials: ResMut<Assets<ColorMaterial>>,
-) {
+fn poll_tasks(mut commands: Commands, mut transform_tasks: Query<(Entity, &mut LevelPending)>) {
   for (entity, mut task) in &mut transform_tasks {
-    if let Some(mesh) = future::block_on(future::poll_once(&mut task.0)) {
+    if let Some(layout) = future::block_on(future::poll_once(&mut task.0)) {
       commands
         .entity(entity)
-        .insert_bundle(MaterialMesh2dBundle {
-          mesh: meshes.add(mesh).into(),
-          transform: Transform::default(),
-          material: materials.add(ColorMaterial::from(Color::PURPLE)),
-          ..default()
-        })
+        .with_children(|parent| spawn_level_entities(parent, &layout))
+        .insert_bundle(SpatialBundle::default())
+        .insert(layout.metrics.clone())
+        .insert(layout)
         .insert(FiniteLevel);
       commands.entity(entity).remove::<LevelPending>();
     }
   }
 }
+
+fn spawn_level_entities(parent: &mut ChildBuilder, layout: &LevelLayout) {
+  // merge each row of solid tiles into as few colliders as possible
+  for y in 0..layout.height {
+    let mut x = 0;
+    while x < layout.width {
+      if layout.get(x, y) != Tile::Solid {
+        x += 1;
+        continue;
+      }
+      let start = x;
+      while x < layout.width && layout.get(x, y) == Tile::Solid {
+        x += 1;
+      }
+      let len = (x - start) as f32;
+      let center = layout.tile_center(UVec2::new(start, y), TILE_SIZE)
+        + Vec2::X * (len - 1.) * TILE_SIZE / 2.;
+
+      parent
+        .spawn_bundle(TransformBundle::from_transform(Transform::from_translation(
+          center.extend(0.),
+        )))
+        .insert(RigidBody::Static)
+        .insert(CollisionShape::Cuboid {
+          half_extends: Vec3::new(len * TILE_SIZE / 2., TILE_SIZE / 2., 0.),
+          border_radius: None,
+        });
+    }
+  }
+
+  for room in layout.secret_rooms.iter() {
+    for tile in room.entrance.iter() {
+      let transform = Transform::from_translation(layout.tile_center(*tile, TILE_SIZE).extend(0.));
+      let mut entrance = parent.spawn_bundle(TransformBundle::from_transform(transform));
+      match layout.get(tile.x, tile.y) {
+        Tile::BreakableWall => {
+          entrance
+            .insert(BreakableWall)
+            .insert(RigidBody::Static)
+            .insert(CollisionShape::Cuboid {
+              half_extends: Vec3::new(TILE_SIZE / 2., TILE_SIZE / 2., 0.),
+              border_radius: None,
+            });
+        }
+        _ => {
+          entrance.insert(FakeFloor);
+        }
+      }
+    }
+  }
+
+  for reward in layout.rewards.iter() {
+    parent
+      .spawn_bundle(TransformBundle::from_transform(Transform::from_translation(
+        layout.tile_center(reward.tile, TILE_SIZE).extend(0.),
+      )))
+      .insert(LevelReward {
+        source: reward.source,
+      });
+  }
+}
@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use game_level_gen::*;
+
+const FLOOR: u32 = 1;
+const CEILING: u32 = 2;
+const WALL: u32 = 3;
+
+fn layout(seed: u64) -> LevelLayout {
+  generate(&GenFiniteLevel {
+    seed,
+    max_jump_height: 3,
+    min_height: 3,
+    max_height: 10,
+    min_width: 60,
+    max_width: 120,
+    branches: 4,
+    secret_rooms: 3,
+    decorations: None,
+  })
+}
+
+fn decoration(tile: u32, placement: Placement) -> Decoration {
+  Decoration {
+    name: format!("{:?}", placement),
+    tile,
+    weight: 1.,
+    placement,
+  }
+}
+
+fn set(density: f64) -> DecorationSet {
+  DecorationSet {
+    texture: "decorations.png".to_string(),
+    tile_size: (32., 32.),
+    density,
+    decorations: vec![
+      decoration(FLOOR, Placement::Floor),
+      decoration(CEILING, Placement::Ceiling),
+      decoration(WALL, Placement::Wall),
+    ],
+    texture_handle: Handle::default(),
+  }
+}
+
+#[test]
+fn scatter_is_deterministic_per_seed() {
+  let layout = layout(3);
+  let set = set(0.5);
+  assert_eq!(scatter(&layout, &set, 3), scatter(&layout, &set, 3));
+  assert_ne!(scatter(&layout, &set, 3), scatter(&layout, &set, 4));
+}
+
+#[test]
+fn decorations_stay_off_occupied_tiles() {
+  for seed in 0..50 {
+    let layout = layout(seed);
+    let placed = scatter(&layout, &set(1.), seed);
+    assert!(!placed.is_empty());
+
+    let solid = |x: Option<u32>, y: Option<u32>| match (x, y) {
+      (Some(x), Some(y)) => layout.get(x, y) == Tile::Solid,
+      _ => false,
+    };
+    for (pos, tile) in placed.iter() {
+      let (x, y) = (pos.x, pos.y);
+      assert_eq!(layout.get(x, y), Tile::Empty, "seed {} at {}", seed, pos);
+      // only plain solid tiles carry decorations, secret entrances stay unmarked
+      let attached = match *tile {
+        FLOOR => solid(Some(x), y.checked_sub(1)),
+        CEILING => solid(Some(x), Some(y + 1)),
+        WALL => solid(x.checked_sub(1), Some(y)) || solid(Some(x + 1), Some(y)),
+        _ => false,
+      };
+      assert!(attached, "seed {} tile {} at {}", seed, tile, pos);
+    }
+    for reward in layout.rewards.iter() {
+      assert!(placed.iter().all(|(pos, _)| *pos != reward.tile), "seed {}", seed);
+    }
+  }
+}
+
+#[test]
+fn density_controls_how_many_surfaces_are_decorated() {
+  let layout = layout(5);
+  assert!(scatter(&layout, &set(0.), 5).is_empty());
+
+  let all = scatter(&layout, &set(1.), 5).len();
+  let half = scatter(&layout, &set(0.5), 5).len();
+  assert!(half > 0 && half < all);
+}
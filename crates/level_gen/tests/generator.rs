@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use game_level_gen::*;
+
+const SEEDS: u64 = 200;
+
+fn config(seed: u64) -> GenFiniteLevel {
+  GenFiniteLevel {
+    seed,
+    max_jump_height: 3,
+    min_height: 3,
+    max_height: 10,
+    min_width: 60,
+    max_width: 120,
+    branches: 4,
+    secret_rooms: 3,
+    decorations: None,
+  }
+}
+
+fn neighbours(tile: UVec2) -> impl Iterator<Item = UVec2> {
+  [(1, 0), (-1, 0), (0, 1), (0, -1)]
+    .into_iter()
+    .filter_map(move |(dx, dy): (i32, i32)| {
+      let x = tile.x.checked_add_signed(dx)?;
+      let y = tile.y.checked_add_signed(dy)?;
+      Some(UVec2::new(x, y))
+    })
+}
+
+fn inside(room: &SecretRoom, tile: UVec2) -> bool {
+  tile.cmpge(room.min).all() && tile.cmple(room.max).all()
+}
+
+#[test]
+fn same_seed_gives_the_same_layout() {
+  for seed in 0..20 {
+    let a = generate(&config(seed));
+    let b = generate(&config(seed));
+    assert_eq!(a.tiles, b.tiles);
+    assert_eq!(a.surface, b.surface);
+    let rewards = |l: &LevelLayout| l.rewards.iter().map(|r| r.tile).collect::<Vec<_>>();
+    assert_eq!(rewards(&a), rewards(&b));
+  }
+
+  let first = generate(&config(0));
+  assert!((1..20).any(|seed| generate(&config(seed)).tiles != first.tiles));
+}
+
+#[test]
+fn critical_path_stays_traversable() {
+  for seed in 0..SEEDS {
+    let config = config(seed);
+    let layout = generate(&config);
+    assert!((config.min_width..=config.max_width).contains(&layout.width));
+    assert_eq!(layout.surface.len() as u32, layout.width);
+
+    for (x, &h) in layout.surface.iter().enumerate() {
+      assert!((config.min_height..=config.max_height).contains(&h), "seed {}", seed);
+      // fake floors are meant to be fallen through, everything else carries the player
+      let ground = layout.get(x as u32, h - 1);
+      assert!(ground.has_collider() || ground == Tile::FakeFloor, "seed {}", seed);
+      assert_eq!(layout.get(x as u32, h), Tile::Empty, "seed {} x {}", seed, x);
+    }
+    for pair in layout.surface.windows(2) {
+      assert!(pair[1] <= pair[0] + config.max_jump_height, "seed {}", seed);
+    }
+  }
+}
+
+#[test]
+fn secret_rooms_are_sealed_except_for_their_entrance() {
+  let mut rooms = 0;
+  for seed in 0..SEEDS {
+    let config = config(seed);
+    let layout = generate(&config);
+    assert!(layout.secret_rooms.len() as u32 <= config.secret_rooms);
+    assert_eq!(layout.metrics.secret_rooms as usize, layout.secret_rooms.len());
+    rooms += layout.secret_rooms.len();
+
+    for room in layout.secret_rooms.iter() {
+      let entrance_tile = match room.kind {
+        SecretKind::BreakableWall => Tile::BreakableWall,
+        SecretKind::FakeFloor => Tile::FakeFloor,
+      };
+      assert!(!room.entrance.is_empty());
+      for tile in room.entrance.iter() {
+        assert_eq!(layout.get(tile.x, tile.y), entrance_tile, "seed {}", seed);
+      }
+
+      for x in room.min.x..=room.max.x {
+        for y in room.min.y..=room.max.y {
+          let tile = UVec2::new(x, y);
+          assert_eq!(layout.get(x, y), Tile::Empty, "seed {}", seed);
+          for n in neighbours(tile) {
+            let sealed = inside(room, n)
+              || room.entrance.contains(&n)
+              || layout.get(n.x, n.y).has_collider();
+            assert!(sealed, "seed {} room {:?} leaks at {}", seed, room.kind, n);
+          }
+        }
+      }
+
+      // the entrance connects the interior with the open level
+      let opens_inwards = room
+        .entrance
+        .iter()
+        .any(|e| neighbours(*e).any(|n| inside(room, n)));
+      let opens_outwards = room.entrance.iter().any(|e| {
+        neighbours(*e).any(|n| {
+          !inside(room, n) && !room.entrance.contains(&n) && layout.get(n.x, n.y) == Tile::Empty
+        })
+      });
+      assert!(opens_inwards && opens_outwards, "seed {} room {:?}", seed, room.kind);
+
+      assert!(layout
+        .rewards
+        .iter()
+        .any(|r| r.source == RewardSource::SecretRoom && inside(room, r.tile)));
+    }
+  }
+  assert!(rooms > 0, "no seed produced a secret room");
+}
+
+#[test]
+fn branches_climb_from_the_critical_path_to_a_reward() {
+  let mut branches = 0;
+  for seed in 0..SEEDS {
+    let config = config(seed);
+    let layout = generate(&config);
+    assert!(layout.branches.len() as u32 <= config.branches);
+    assert_eq!(layout.metrics.optional_branches as usize, layout.branches.len());
+    branches += layout.branches.len();
+
+    let branch_rewards: Vec<_> = layout
+      .rewards
+      .iter()
+      .filter(|r| r.source == RewardSource::Branch)
+      .collect();
+    assert_eq!(branch_rewards.len(), layout.branches.len());
+
+    for branch in layout.branches.iter() {
+      assert_eq!(branch.anchor.y, layout.surface[branch.anchor.x as usize]);
+      assert_eq!(branch.platforms[0].x, branch.anchor.x + 1);
+
+      let mut stand = branch.anchor.y;
+      for platform in branch.platforms.iter() {
+        assert_eq!(layout.get(platform.x, platform.y), Tile::Solid);
+        if platform.y + 1 != stand {
+          assert!(platform.y < stand + config.max_jump_height, "seed {}", seed);
+          stand = platform.y + 1;
+        }
+      }
+    }
+
+    for reward in branch_rewards {
+      assert_eq!(layout.get(reward.tile.x, reward.tile.y), Tile::Empty);
+      assert_eq!(layout.get(reward.tile.x, reward.tile.y - 1), Tile::Solid);
+    }
+  }
+  assert!(branches > 0, "no seed produced a branch");
+}
@@ -0,0 +1,223 @@
+use bevy::{prelude::*, utils::HashMap};
+use game_utils::GameRng;
+use rand::Rng;
+
+mod preset;
+pub use preset::*;
+
+#[derive(SystemLabel, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ParticleSystem {
+  Update,
+  Emit,
+}
+
+/// Spawns particles described by a preset at its `GlobalTransform`. Particles live in world space
+/// so they stay behind when the emitter moves.
+#[derive(Component)]
+pub struct ParticleEmitter {
+  pub preset: Handle<ParticlePreset>,
+  pub active: bool,
+  // despawn the emitter once it has stopped emitting and its particles are gone
+  pub despawn_when_done: bool,
+  elapsed: f32,
+  // fractional particles owed by a continuous emitter
+  pending: f32,
+  burst_fired: bool,
+}
+
+impl ParticleEmitter {
+  pub fn new(preset: Handle<ParticlePreset>) -> Self {
+    Self {
+      preset,
+      active: true,
+      despawn_when_done: false,
+      elapsed: 0.,
+      pending: 0.,
+      burst_fired: false,
+    }
+  }
+
+  /// For fire and forget effects like dust or hit sparks.
+  pub fn once(mut self) -> Self {
+    self.despawn_when_done = true;
+    self
+  }
+
+  /// Starts over, firing bursts again.
+  pub fn restart(&mut self) {
+    self.active = true;
+    self.elapsed = 0.;
+    self.pending = 0.;
+    self.burst_fired = false;
+  }
+
+  fn finished_emitting(&self, preset: &ParticlePreset) -> bool {
+    match preset.mode {
+      EmitMode::Burst { .. } => self.burst_fired,
+      EmitMode::Continuous { .. } => preset.duration.is_some_and(|d| self.elapsed >= d),
+    }
+  }
+}
+
+#[derive(Component)]
+pub struct Particle {
+  pub emitter: Entity,
+  pub velocity: Vec2,
+  pub age: f32,
+  pub lifetime: f32,
+  preset: Handle<ParticlePreset>,
+}
+
+fn range(rng: &mut impl Rng, (min, max): (f32, f32)) -> f32 {
+  if min < max {
+    rng.gen_range(min..max)
+  } else {
+    min
+  }
+}
+
+fn emit_particles(
+  mut commands: Commands,
+  time: Res<Time>,
+  presets: Res<Assets<ParticlePreset>>,
+  mut rng: ResMut<GameRng>,
+  mut emitters: Query<(Entity, &mut ParticleEmitter, &GlobalTransform)>,
+  particles: Query<&Particle>,
+) {
+  let mut alive: HashMap<Entity, usize> = HashMap::default();
+  for particle in particles.iter() {
+    *alive.entry(particle.emitter).or_default() += 1;
+  }
+
+  for (entity, mut emitter, transform) in emitters.iter_mut() {
+    let preset = match presets.get(&emitter.preset) {
+      Some(preset) => preset,
+      None => continue,
+    };
+    let alive = alive.get(&entity).copied().unwrap_or(0);
+    if !emitter.active || emitter.finished_emitting(preset) {
+      if emitter.despawn_when_done && alive == 0 {
+        commands.entity(entity).despawn_recursive();
+      }
+      continue;
+    }
+
+    let count = match preset.mode {
+      EmitMode::Burst { count } => {
+        emitter.burst_fired = true;
+        count as usize
+      }
+      EmitMode::Continuous { rate } => {
+        let dt = match preset.duration {
+          Some(duration) => time.delta_seconds().min(duration - emitter.elapsed),
+          None => time.delta_seconds(),
+        };
+        emitter.pending += rate.max(0.) * dt;
+        let count = emitter.pending.floor();
+        emitter.pending -= count;
+        count as usize
+      }
+    };
+    emitter.elapsed += time.delta_seconds();
+
+    let origin = transform.translation();
+    for _ in 0..count.min(preset.max_particles.saturating_sub(alive)) {
+      let spread = range(&mut *rng, (-preset.spread, preset.spread));
+      let angle = (preset.direction + spread).to_radians();
+      let velocity = Vec2::new(angle.cos(), angle.sin()) * range(&mut *rng, preset.speed);
+      let particle = Particle {
+        emitter: entity,
+        velocity,
+        age: 0.,
+        lifetime: range(&mut *rng, preset.lifetime),
+        preset: emitter.preset.clone(),
+      };
+      let transform = Transform::from_translation(origin);
+      let size = Some(Vec2::splat(preset.size_at(0.)));
+      let color = preset.color_at(0.);
+
+      match preset.atlas_handle.as_ref() {
+        Some(atlas) => commands.spawn_bundle(SpriteSheetBundle {
+          sprite: TextureAtlasSprite {
+            index: preset.frame_at(0.),
+            color,
+            custom_size: size,
+            ..default()
+          },
+          texture_atlas: atlas.clone(),
+          transform,
+          ..default()
+        }),
+        None => commands.spawn_bundle(SpriteBundle {
+          sprite: Sprite {
+            color,
+            custom_size: size,
+            ..default()
+          },
+          transform,
+          ..default()
+        }),
+      }
+      .insert(particle);
+    }
+  }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_particles(
+  mut commands: Commands,
+  time: Res<Time>,
+  presets: Res<Assets<ParticlePreset>>,
+  mut particles: Query<(
+    Entity,
+    &mut Particle,
+    &mut Transform,
+    Option<&mut Sprite>,
+    Option<&mut TextureAtlasSprite>,
+  )>,
+) {
+  let dt = time.delta_seconds();
+  for (entity, mut particle, mut transform, sprite, atlas_sprite) in particles.iter_mut() {
+    particle.age += dt;
+    let preset = match presets.get(&particle.preset) {
+      Some(preset) if particle.age < particle.lifetime => preset,
+      _ => {
+        commands.entity(entity).despawn();
+        continue;
+      }
+    };
+
+    particle.velocity += Vec2::new(preset.gravity.0, preset.gravity.1) * dt;
+    transform.translation += (particle.velocity * dt).extend(0.);
+
+    let t = particle.age / particle.lifetime;
+    let color = preset.color_at(t);
+    let size = Some(Vec2::splat(preset.size_at(t)));
+    if let Some(mut sprite) = sprite {
+      sprite.color = color;
+      sprite.custom_size = size;
+    }
+    if let Some(mut sprite) = atlas_sprite {
+      sprite.color = color;
+      sprite.custom_size = size;
+      sprite.index = preset.frame_at(t);
+    }
+  }
+}
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<GameRng>()
+      .add_asset::<ParticlePreset>()
+      .init_asset_loader::<ParticlePresetLoader>()
+      .add_system(update_particles.label(ParticleSystem::Update))
+      .add_system(
+        emit_particles
+          .label(ParticleSystem::Emit)
+          .after(ParticleSystem::Update),
+      );
+  }
+}
@@ -0,0 +1,164 @@
+use anyhow::anyhow;
+use bevy::{
+  asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+  prelude::*,
+  reflect::TypeUuid,
+  utils::BoxedFuture,
+};
+use game_utils::Lerp;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum EmitMode {
+  // everything at once when the emitter starts
+  Burst { count: u32 },
+  // particles per second
+  Continuous { rate: f32 },
+}
+
+/// Particle textures cut from a grid. `frames` are atlas indices played over each particle's life.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ParticleAtlas {
+  pub texture: String,
+  pub tile_size: (f32, f32),
+  pub columns: usize,
+  pub rows: usize,
+  pub frames: Vec<usize>,
+}
+
+/// Everything about how an emitter's particles look and move, loaded from `particles.ron` files.
+/// Ranges are `(min, max)` and picked from uniformly per particle, curves are keyframes over a
+/// particle's life from 0 to 1.
+#[derive(Clone, Debug, Deserialize, TypeUuid)]
+#[uuid = "3a7e9c14-52b8-4f0d-a6e1-9d2c7b4f8e53"]
+pub struct ParticlePreset {
+  pub mode: EmitMode,
+  // how long a continuous emitter runs, forever without it
+  #[serde(default)]
+  pub duration: Option<f32>,
+  pub max_particles: usize,
+  pub lifetime: (f32, f32),
+  pub speed: (f32, f32),
+  // degrees, 0 points right and 90 up
+  pub direction: f32,
+  // degrees either side of `direction`
+  pub spread: f32,
+  #[serde(default)]
+  pub gravity: (f32, f32),
+  // rgba
+  pub color: Vec<(f32, (f32, f32, f32, f32))>,
+  // pixels
+  pub size: Vec<(f32, f32)>,
+  #[serde(default)]
+  pub atlas: Option<ParticleAtlas>,
+  #[serde(skip)]
+  pub atlas_handle: Option<Handle<TextureAtlas>>,
+}
+
+// Keyframes are expected in order, values before the first and after the last key hold.
+pub(crate) fn sample<V: Lerp>(keys: &[(f32, V)], t: f32) -> Option<V> {
+  let after = keys.iter().position(|(at, _)| *at > t);
+  match after {
+    Some(0) => keys.first().map(|(_, v)| v.clone()),
+    Some(i) => {
+      let ((from_at, from), (to_at, to)) = (&keys[i - 1], &keys[i]);
+      Some(from.lerp(to, (t - from_at) / (to_at - from_at)))
+    }
+    None => keys.last().map(|(_, v)| v.clone()),
+  }
+}
+
+fn increasing(mut keys: impl Iterator<Item = f32>) -> bool {
+  let mut last = f32::NEG_INFINITY;
+  keys.all(|at| {
+    let ok = at > last;
+    last = at;
+    ok
+  })
+}
+
+impl ParticlePreset {
+  pub fn color_at(&self, t: f32) -> Color {
+    let keys: Vec<_> = self
+      .color
+      .iter()
+      .map(|(at, (r, g, b, a))| (*at, Color::rgba(*r, *g, *b, *a)))
+      .collect();
+    sample(&keys, t).unwrap_or(Color::WHITE)
+  }
+
+  pub fn size_at(&self, t: f32) -> f32 {
+    sample(&self.size, t).unwrap_or(1.)
+  }
+
+  pub fn frame_at(&self, t: f32) -> usize {
+    match self.atlas.as_ref() {
+      Some(atlas) if !atlas.frames.is_empty() => {
+        let i = (t * atlas.frames.len() as f32) as usize;
+        atlas.frames[i.min(atlas.frames.len() - 1)]
+      }
+      _ => 0,
+    }
+  }
+
+  fn validate(&self) -> Result<(), anyhow::Error> {
+    let ordered = |range: (f32, f32)| range.0 <= range.1;
+    if !ordered(self.lifetime) || self.lifetime.0 <= 0. {
+      return Err(anyhow!("lifetime must be a positive (min, max) range"));
+    }
+    if !ordered(self.speed) {
+      return Err(anyhow!("speed must be a (min, max) range"));
+    }
+    if !increasing(self.color.iter().map(|(at, _)| *at))
+      || !increasing(self.size.iter().map(|(at, _)| *at))
+    {
+      return Err(anyhow!("curve keyframes must be in increasing order"));
+    }
+    if let Some(atlas) = self.atlas.as_ref() {
+      let len = atlas.columns * atlas.rows;
+      if let Some(frame) = atlas.frames.iter().find(|f| **f >= len) {
+        return Err(anyhow!("frame {} is outside the atlas of {} frames", frame, len));
+      }
+    }
+    Ok(())
+  }
+}
+
+#[derive(Default)]
+pub struct ParticlePresetLoader;
+
+impl AssetLoader for ParticlePresetLoader {
+  fn load<'a>(
+    &'a self,
+    bytes: &'a [u8],
+    load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+    Box::pin(async move {
+      let mut preset: ParticlePreset = ron::de::from_bytes(bytes)?;
+      preset.validate()?;
+
+      let mut dependencies = Vec::new();
+      if let Some(atlas) = preset.atlas.as_ref() {
+        let texture_path = AssetPath::from(atlas.texture.as_str()).to_owned();
+        let texture = load_context.get_handle(texture_path.clone());
+        let texture_atlas = TextureAtlas::from_grid(
+          texture,
+          Vec2::new(atlas.tile_size.0, atlas.tile_size.1),
+          atlas.columns,
+          atlas.rows,
+        );
+        preset.atlas_handle = Some(load_context.set_labeled_asset(
+          "atlas",
+          LoadedAsset::new(texture_atlas).with_dependency(texture_path.clone()),
+        ));
+        dependencies.push(texture_path);
+      }
+      load_context.set_default_asset(LoadedAsset::new(preset).with_dependencies(dependencies));
+      Ok(())
+    })
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &["particles.ron"]
+  }
+}
@@ -0,0 +1,113 @@
+use bevy::{asset::AssetPlugin, prelude::*};
+use game_particles::*;
+use game_utils::{GameRng, SteppedApp};
+
+struct Harness {
+  app: SteppedApp,
+}
+
+impl Harness {
+  fn new() -> Self {
+    let mut app = SteppedApp::new();
+    app
+      .insert_resource(GameRng::seeded(0))
+      .add_plugin(AssetPlugin)
+      .add_plugin(ParticlePlugin);
+    Self { app }
+  }
+
+  fn spawn(&mut self, preset: ParticlePreset, once: bool) -> Entity {
+    let handle = self
+      .app
+      .world
+      .resource_mut::<Assets<ParticlePreset>>()
+      .add(preset);
+    let emitter = ParticleEmitter::new(handle);
+    let emitter = if once { emitter.once() } else { emitter };
+    self
+      .app
+      .world
+      .spawn()
+      .insert_bundle(TransformBundle::default())
+      .insert(emitter)
+      .id()
+  }
+
+  fn step(&mut self) {
+    self.app.step();
+  }
+
+  fn particles(&mut self) -> usize {
+    self.app.world.query::<&Particle>().iter(&self.app.world).count()
+  }
+}
+
+fn preset(mode: EmitMode) -> ParticlePreset {
+  ParticlePreset {
+    mode,
+    duration: None,
+    max_particles: 100,
+    lifetime: (0.6, 0.6),
+    speed: (10., 10.),
+    direction: 90.,
+    spread: 0.,
+    gravity: (0., 0.),
+    color: vec![(0., (1., 1., 1., 1.)), (1., (1., 1., 1., 0.))],
+    size: vec![(0., 2.), (1., 4.)],
+    atlas: None,
+    atlas_handle: None,
+  }
+}
+
+#[test]
+fn burst_fires_once_and_cleans_up() {
+  let mut h = Harness::new();
+  let emitter = h.spawn(preset(EmitMode::Burst { count: 5 }), true);
+
+  h.step();
+  assert_eq!(h.particles(), 5);
+  h.step();
+  assert_eq!(h.particles(), 5);
+
+  // the particles expire, then the emitter goes with them
+  h.step();
+  h.step();
+  assert_eq!(h.particles(), 0);
+  h.step();
+  assert!(h.app.world.get_entity(emitter).is_none());
+}
+
+#[test]
+fn continuous_emitter_respects_rate_and_cap() {
+  let mut h = Harness::new();
+  h.spawn(
+    ParticlePreset {
+      max_particles: 3,
+      lifetime: (10., 10.),
+      ..preset(EmitMode::Continuous { rate: 8. })
+    },
+    false,
+  );
+
+  h.step();
+  assert_eq!(h.particles(), 2);
+  h.step();
+  assert_eq!(h.particles(), 3);
+}
+
+#[test]
+fn particles_move_and_fade_over_their_life() {
+  let mut h = Harness::new();
+  h.spawn(preset(EmitMode::Burst { count: 1 }), false);
+  h.step();
+  h.step();
+
+  let (transform, sprite) = h
+    .app
+    .world
+    .query_filtered::<(&Transform, &Sprite), With<Particle>>()
+    .single(&h.app.world);
+  assert!(transform.translation.y > 0.);
+  assert!(sprite.color.a() < 1.);
+  assert!(sprite.custom_size.unwrap().x > 2.);
+}
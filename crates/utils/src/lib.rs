@@ -1,7 +1,49 @@
 use bevy::prelude::*;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+#[cfg(feature = "test-util")]
+mod test_util;
+mod tween;
+#[cfg(feature = "test-util")]
+pub use test_util::*;
+pub use tween::*;
 
 pub fn cleanup_system<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
   for entity in to_despawn.iter() {
     commands.entity(entity).despawn_recursive();
   }
 }
+
+/// Game-wide random number generator. Insert a seeded one before adding plugins that use it to
+/// make runs reproducible, otherwise it is seeded from entropy.
+pub struct GameRng(StdRng);
+
+impl GameRng {
+  pub fn seeded(seed: u64) -> Self {
+    Self(StdRng::seed_from_u64(seed))
+  }
+}
+
+impl Default for GameRng {
+  fn default() -> Self {
+    Self(StdRng::from_entropy())
+  }
+}
+
+impl RngCore for GameRng {
+  fn next_u32(&mut self) -> u32 {
+    self.0.next_u32()
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    self.0.next_u64()
+  }
+
+  fn fill_bytes(&mut self, dest: &mut [u8]) {
+    self.0.fill_bytes(dest)
+  }
+
+  fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+    self.0.try_fill_bytes(dest)
+  }
+}
@@ -0,0 +1,71 @@
+use bevy::{
+  prelude::*,
+  tasks::{AsyncComputeTaskPool, ComputeTaskPool, IoTaskPool, TaskPool},
+  utils::{Duration, Instant},
+};
+use std::ops::{Deref, DerefMut};
+
+/// Seconds each [`SteppedApp::step`] lets pass. Exactly representable, so steps add up to exact
+/// frame, fade and tween boundaries.
+pub const STEP: f32 = 0.25;
+
+/// A headless app driven in fixed steps of time, for testing time-based systems.
+pub struct SteppedApp {
+  app: App,
+  now: Instant,
+}
+
+impl SteppedApp {
+  /// An app with `Time` and the task pools asset plugins expect, add the plugins under test to it.
+  pub fn new() -> Self {
+    ComputeTaskPool::init(TaskPool::default);
+    AsyncComputeTaskPool::init(TaskPool::default);
+    IoTaskPool::init(TaskPool::default);
+
+    let mut app = App::new();
+    app.init_resource::<Time>();
+    let now = Instant::now();
+    app.world.resource_mut::<Time>().update_with_instant(now);
+    Self { app, now }
+  }
+
+  /// Runs the systems without letting any time pass.
+  pub fn settle(&mut self) {
+    self.advance(Duration::ZERO);
+  }
+
+  pub fn step(&mut self) {
+    self.advance(Duration::from_secs_f32(STEP));
+  }
+
+  fn advance(&mut self, delta: Duration) {
+    self.now += delta;
+    let now = self.now;
+    self
+      .app
+      .world
+      .resource_mut::<Time>()
+      .update_with_instant(now);
+    self.app.update();
+  }
+}
+
+impl Default for SteppedApp {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Deref for SteppedApp {
+  type Target = App;
+
+  fn deref(&self) -> &App {
+    &self.app
+  }
+}
+
+impl DerefMut for SteppedApp {
+  fn deref_mut(&mut self) -> &mut App {
+    &mut self.app
+  }
+}
@@ -0,0 +1,378 @@
+use bevy::{ecs::system::Command, prelude::*};
+use std::{
+  f32::consts::PI,
+  marker::PhantomData,
+  sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Easing curves, mapping linear progress in `0..=1` to eased progress.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Ease {
+  #[default]
+  Linear,
+  QuadIn,
+  QuadOut,
+  QuadInOut,
+  CubicIn,
+  CubicOut,
+  CubicInOut,
+  SineInOut,
+  // overshoots the end slightly before settling
+  BackOut,
+  BounceOut,
+}
+
+impl Ease {
+  pub fn apply(self, t: f32) -> f32 {
+    let t = t.clamp(0., 1.);
+    match self {
+      Ease::Linear => t,
+      Ease::QuadIn => t * t,
+      Ease::QuadOut => 1. - (1. - t) * (1. - t),
+      Ease::QuadInOut if t < 0.5 => 2. * t * t,
+      Ease::QuadInOut => 1. - (-2. * t + 2.).powi(2) / 2.,
+      Ease::CubicIn => t * t * t,
+      Ease::CubicOut => 1. - (1. - t).powi(3),
+      Ease::CubicInOut if t < 0.5 => 4. * t * t * t,
+      Ease::CubicInOut => 1. - (-2. * t + 2.).powi(3) / 2.,
+      Ease::SineInOut => -((PI * t).cos() - 1.) / 2.,
+      Ease::BackOut => {
+        let c1 = 1.70158;
+        1. + (c1 + 1.) * (t - 1.).powi(3) + c1 * (t - 1.).powi(2)
+      }
+      Ease::BounceOut => {
+        let (n, d) = (7.5625, 2.75);
+        if t < 1. / d {
+          n * t * t
+        } else if t < 2. / d {
+          let t = t - 1.5 / d;
+          n * t * t + 0.75
+        } else if t < 2.5 / d {
+          let t = t - 2.25 / d;
+          n * t * t + 0.9375
+        } else {
+          let t = t - 2.625 / d;
+          n * t * t + 0.984375
+        }
+      }
+    }
+  }
+}
+
+/// Values a tween can interpolate between.
+pub trait Lerp: Clone + Send + Sync + 'static {
+  fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+  fn lerp(&self, to: &Self, t: f32) -> Self {
+    self + (to - self) * t
+  }
+}
+
+impl Lerp for Vec3 {
+  fn lerp(&self, to: &Self, t: f32) -> Self {
+    Vec3::lerp(*self, *to, t)
+  }
+}
+
+impl Lerp for Quat {
+  fn lerp(&self, to: &Self, t: f32) -> Self {
+    self.slerp(*to, t)
+  }
+}
+
+impl Lerp for Color {
+  fn lerp(&self, to: &Self, t: f32) -> Self {
+    let (from, to) = (self.as_rgba_f32(), to.as_rgba_f32());
+    Color::rgba(
+      from[0].lerp(&to[0], t),
+      from[1].lerp(&to[1], t),
+      from[2].lerp(&to[2], t),
+      from[3].lerp(&to[3], t),
+    )
+  }
+}
+
+// Values in different units can't be blended, so they switch over at the end.
+impl Lerp for Val {
+  fn lerp(&self, to: &Self, t: f32) -> Self {
+    match (self, to) {
+      (Val::Px(from), Val::Px(to)) => Val::Px(from.lerp(to, t)),
+      (Val::Percent(from), Val::Percent(to)) => Val::Percent(from.lerp(to, t)),
+      _ if t < 1. => *self,
+      _ => *to,
+    }
+  }
+}
+
+impl Lerp for Size<Val> {
+  fn lerp(&self, to: &Self, t: f32) -> Self {
+    Size::new(self.width.lerp(&to.width, t), self.height.lerp(&to.height, t))
+  }
+}
+
+/// One value of a component that can be tweened, the rest of the component is left alone.
+pub trait Tweenable: Send + Sync + 'static {
+  type Component: Component;
+  type Value: Lerp;
+
+  fn set(component: &mut Self::Component, value: Self::Value);
+}
+
+/// Tweens a `Transform`'s translation, its rotation and scale stay free to change.
+pub struct Translation;
+
+impl Tweenable for Translation {
+  type Component = Transform;
+  type Value = Vec3;
+
+  fn set(component: &mut Transform, value: Vec3) {
+    component.translation = value;
+  }
+}
+
+/// Tweens a `Transform`'s rotation.
+pub struct Rotation;
+
+impl Tweenable for Rotation {
+  type Component = Transform;
+  type Value = Quat;
+
+  fn set(component: &mut Transform, value: Quat) {
+    component.rotation = value;
+  }
+}
+
+/// Tweens a `Transform`'s scale, so squashing a moving body doesn't pin it in place.
+pub struct Scale;
+
+impl Tweenable for Scale {
+  type Component = Transform;
+  type Value = Vec3;
+
+  fn set(component: &mut Transform, value: Vec3) {
+    component.scale = value;
+  }
+}
+
+impl Tweenable for Sprite {
+  type Component = Sprite;
+  type Value = Color;
+
+  fn set(component: &mut Sprite, value: Color) {
+    component.color = value;
+  }
+}
+
+impl Tweenable for UiColor {
+  type Component = UiColor;
+  type Value = Color;
+
+  fn set(component: &mut UiColor, value: Color) {
+    component.0 = value;
+  }
+}
+
+impl Tweenable for Style {
+  type Component = Style;
+  type Value = Size<Val>;
+
+  fn set(component: &mut Style, value: Size<Val>) {
+    component.size = value;
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TweenRepeat {
+  Once,
+  // total number of passes, with yoyo each direction counts as one
+  Times(u32),
+  Forever,
+}
+
+static NEXT_SERIAL: AtomicU64 = AtomicU64::new(0);
+
+struct TweenStep<V> {
+  start: V,
+  end: V,
+  duration: f32,
+  ease: Ease,
+}
+
+/// Moves a component's value through a sequence of eased steps, then removes itself and sends a
+/// `TweenCompleted`. Inserting a new tween replaces the running one.
+#[derive(Component)]
+pub struct Tween<C: Tweenable> {
+  steps: Vec<TweenStep<C::Value>>,
+  repeat: TweenRepeat,
+  yoyo: bool,
+  // sent back with the completion event to tell tweens apart
+  id: u32,
+  // unique per tween, so finishing doesn't remove a replacement inserted in the same frame
+  serial: u64,
+  elapsed: f32,
+  passes: u32,
+  backwards: bool,
+}
+
+impl<C: Tweenable> Tween<C> {
+  pub fn new(start: C::Value, end: C::Value, duration: f32, ease: Ease) -> Self {
+    Self {
+      steps: vec![TweenStep {
+        start,
+        end,
+        duration,
+        ease,
+      }],
+      repeat: TweenRepeat::Once,
+      yoyo: false,
+      id: 0,
+      serial: NEXT_SERIAL.fetch_add(1, Ordering::Relaxed),
+      elapsed: 0.,
+      passes: 0,
+      backwards: false,
+    }
+  }
+
+  /// Continues from where the previous step ended. Use the same value to hold it for a while.
+  pub fn then(mut self, end: C::Value, duration: f32, ease: Ease) -> Self {
+    let start = self.steps.last().expect("a tween has a step").end.clone();
+    self.steps.push(TweenStep {
+      start,
+      end,
+      duration,
+      ease,
+    });
+    self
+  }
+
+  pub fn repeat(mut self, repeat: TweenRepeat) -> Self {
+    self.repeat = repeat;
+    self
+  }
+
+  /// Plays every other pass backwards.
+  pub fn yoyo(mut self) -> Self {
+    self.yoyo = true;
+    self
+  }
+
+  pub fn with_id(mut self, id: u32) -> Self {
+    self.id = id;
+    self
+  }
+
+  pub fn id(&self) -> u32 {
+    self.id
+  }
+
+  fn pass_duration(&self) -> f32 {
+    self.steps.iter().map(|s| s.duration.max(0.)).sum()
+  }
+
+  // Advances time and returns the current value and whether the tween is done.
+  fn tick(&mut self, delta: f32) -> (C::Value, bool) {
+    let pass = self.pass_duration();
+    let mut finished = pass <= 0.;
+    self.elapsed += delta;
+    while !finished && self.elapsed >= pass {
+      self.passes += 1;
+      finished = match self.repeat {
+        TweenRepeat::Once => true,
+        TweenRepeat::Times(n) => self.passes >= n,
+        TweenRepeat::Forever => false,
+      };
+      if finished {
+        self.elapsed = pass;
+      } else {
+        self.elapsed -= pass;
+        if self.yoyo {
+          self.backwards = !self.backwards;
+        }
+      }
+    }
+
+    let mut position = if self.backwards {
+      pass - self.elapsed
+    } else {
+      self.elapsed
+    };
+    for (i, step) in self.steps.iter().enumerate() {
+      let duration = step.duration.max(0.);
+      if position < duration || i == self.steps.len() - 1 {
+        let t = if duration > 0. { position / duration } else { 1. };
+        return (step.start.lerp(&step.end, step.ease.apply(t)), finished);
+      }
+      position -= duration;
+    }
+    unreachable!("a tween has a step")
+  }
+}
+
+/// Sent when a tween has played all its passes, right after it is removed.
+pub struct TweenCompleted {
+  pub entity: Entity,
+  pub id: u32,
+}
+
+// Removes a finished tween unless another one was inserted in its place since.
+struct RemoveTween<C> {
+  entity: Entity,
+  serial: u64,
+  phantom: PhantomData<C>,
+}
+
+impl<C: Tweenable> Command for RemoveTween<C> {
+  fn write(self, world: &mut World) {
+    if let Some(mut entity) = world.get_entity_mut(self.entity) {
+      if entity.get::<Tween<C>>().map(|t| t.serial) == Some(self.serial) {
+        entity.remove::<Tween<C>>();
+      }
+    }
+  }
+}
+
+fn animate_tweens<C: Tweenable>(
+  mut commands: Commands,
+  time: Res<Time>,
+  mut events: EventWriter<TweenCompleted>,
+  mut qry: Query<(Entity, &mut Tween<C>, &mut C::Component)>,
+) {
+  for (entity, mut tween, mut component) in qry.iter_mut() {
+    let (value, finished) = tween.tick(time.delta_seconds());
+    C::set(&mut component, value);
+    if finished {
+      commands.add(RemoveTween::<C> {
+        entity,
+        serial: tween.serial,
+        phantom: PhantomData,
+      });
+      events.send(TweenCompleted {
+        entity,
+        id: tween.id,
+      });
+    }
+  }
+}
+
+/// Label of the systems that advance tweens.
+#[derive(SystemLabel, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TweenSystem;
+
+/// Runs tweens on `Transform` translations, rotations and scales, `Sprite` colours, `UiColor` and
+/// `Style` sizes.
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_event::<TweenCompleted>()
+      .add_system(animate_tweens::<Translation>.label(TweenSystem))
+      .add_system(animate_tweens::<Rotation>.label(TweenSystem))
+      .add_system(animate_tweens::<Scale>.label(TweenSystem))
+      .add_system(animate_tweens::<Sprite>.label(TweenSystem))
+      .add_system(animate_tweens::<UiColor>.label(TweenSystem))
+      .add_system(animate_tweens::<Style>.label(TweenSystem));
+  }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use game_utils::*;
+
+struct Harness {
+  app: SteppedApp,
+}
+
+impl Harness {
+  fn new() -> Self {
+    let mut app = SteppedApp::new();
+    app.add_plugin(TweenPlugin);
+    Self { app }
+  }
+
+  fn step(&mut self) {
+    self.app.step();
+  }
+
+  fn x(&self, entity: Entity) -> f32 {
+    self
+      .app
+      .world
+      .get::<Transform>(entity)
+      .unwrap()
+      .translation
+      .x
+  }
+
+  fn xs(&mut self, entity: Entity, steps: usize) -> Vec<f32> {
+    (0..steps)
+      .map(|_| {
+        self.step();
+        self.x(entity)
+      })
+      .collect()
+  }
+
+  fn completed(&self) -> Vec<(Entity, u32)> {
+    let events = self.app.world.resource::<Events<TweenCompleted>>();
+    events
+      .get_reader()
+      .iter(events)
+      .map(|ev| (ev.entity, ev.id))
+      .collect()
+  }
+}
+
+fn at(x: f32) -> Vec3 {
+  Vec3::new(x, 0., 0.)
+}
+
+#[test]
+fn easing_curves_start_at_zero_and_end_at_one() {
+  for ease in [
+    Ease::Linear,
+    Ease::QuadIn,
+    Ease::QuadOut,
+    Ease::QuadInOut,
+    Ease::CubicIn,
+    Ease::CubicOut,
+    Ease::CubicInOut,
+    Ease::SineInOut,
+    Ease::BackOut,
+    Ease::BounceOut,
+  ] {
+    assert!(ease.apply(0.).abs() < 1e-5, "{:?}", ease);
+    assert!((ease.apply(1.) - 1.).abs() < 1e-5, "{:?}", ease);
+  }
+}
+
+#[test]
+fn sequence_plays_steps_in_order_then_completes() {
+  let mut h = Harness::new();
+  let tween = Tween::<Translation>::new(at(0.), at(4.), 1., Ease::Linear)
+    .then(at(0.), 0.5, Ease::Linear)
+    .with_id(3);
+  let entity = h
+    .app
+    .world
+    .spawn()
+    .insert(Transform::default())
+    .insert(tween)
+    .id();
+
+  assert_eq!(h.xs(entity, 6), vec![1., 2., 3., 4., 2., 0.]);
+  assert_eq!(h.completed(), vec![(entity, 3)]);
+  assert!(h.app.world.get::<Tween<Translation>>(entity).is_none());
+}
+
+#[test]
+fn yoyo_comes_back_before_completing() {
+  let mut h = Harness::new();
+  let tween = Tween::<Translation>::new(at(0.), at(2.), 0.5, Ease::Linear)
+    .repeat(TweenRepeat::Times(2))
+    .yoyo();
+  let entity = h
+    .app
+    .world
+    .spawn()
+    .insert(Transform::default())
+    .insert(tween)
+    .id();
+
+  assert_eq!(h.xs(entity, 3), vec![1., 2., 1.]);
+  assert!(h.completed().is_empty());
+  assert_eq!(h.xs(entity, 1), vec![0.]);
+  assert_eq!(h.completed(), vec![(entity, 0)]);
+}
+
+#[derive(Default)]
+struct Replacement(Option<(Entity, Tween<Translation>)>);
+
+// inserts the pending tween from a system, like a UI reacting to input would
+fn insert_replacement(mut commands: Commands, mut replacement: ResMut<Replacement>) {
+  if let Some((entity, tween)) = replacement.0.take() {
+    commands.entity(entity).insert(tween);
+  }
+}
+
+#[test]
+fn replacing_a_tween_as_it_finishes_keeps_the_new_one() {
+  for before in [true, false] {
+    let mut h = Harness::new();
+    h.app.init_resource::<Replacement>();
+    if before {
+      h.app.add_system(insert_replacement.before(TweenSystem));
+    } else {
+      h.app.add_system(insert_replacement.after(TweenSystem));
+    }
+    let tween = Tween::<Translation>::new(at(0.), at(1.), 0.25, Ease::Linear).with_id(1);
+    let entity = h
+      .app
+      .world
+      .spawn()
+      .insert(Transform::default())
+      .insert(tween)
+      .id();
+
+    let replacement = Tween::<Translation>::new(at(1.), at(3.), 0.5, Ease::Linear).with_id(2);
+    h.app.world.resource_mut::<Replacement>().0 = Some((entity, replacement));
+    h.step();
+    assert_eq!(h.completed(), vec![(entity, 1)]);
+    assert!(h.app.world.get::<Tween<Translation>>(entity).is_some());
+
+    assert_eq!(h.xs(entity, 2), vec![2., 3.]);
+    assert_eq!(h.completed(), vec![(entity, 2)]);
+  }
+}
+
+// moves the entity along y, like physics would
+fn fall(mut qry: Query<&mut Transform>) {
+  for mut transform in qry.iter_mut() {
+    transform.translation.y -= 1.;
+  }
+}
+
+#[test]
+fn tweening_one_field_leaves_the_others_alone() {
+  let mut h = Harness::new();
+  h.app.add_system(fall.before(TweenSystem));
+  let squash = Tween::<Scale>::new(Vec3::ONE, Vec3::new(2., 0.5, 1.), 0.5, Ease::Linear);
+  let turn = Tween::<Rotation>::new(Quat::IDENTITY, Quat::from_rotation_z(1.), 0.5, Ease::Linear);
+  let entity = h
+    .app
+    .world
+    .spawn()
+    .insert(Transform::from_xyz(3., 0., 0.))
+    .insert(squash)
+    .insert(turn)
+    .id();
+
+  h.step();
+  h.step();
+  let transform = h.app.world.get::<Transform>(entity).unwrap();
+  assert_eq!(transform.translation, Vec3::new(3., -2., 0.));
+  assert_eq!(transform.scale, Vec3::new(2., 0.5, 1.));
+  assert!(transform
+    .rotation
+    .abs_diff_eq(Quat::from_rotation_z(1.), 1e-5));
+}
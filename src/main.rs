@@ -1,4 +1,4 @@
-use bevy::{prelude::*, render::texture::ImageSettings};
+use bevy::{asset::AssetServerSettings, prelude::*, render::texture::ImageSettings};
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash, Copy)]
 enum GameState {
@@ -14,6 +14,10 @@ mod splash;
 fn main() {
   App::new()
     .insert_resource(ImageSettings::default_nearest())
+    .insert_resource(AssetServerSettings {
+      watch_for_changes: cfg!(all(debug_assertions, not(target_arch = "wasm32"))),
+      ..default()
+    })
     .insert_resource(WindowDescriptor {
       title: "Let it be done!".to_string(),
       width: 1920.,
@@ -27,12 +31,14 @@ fn main() {
     )))
     .add_state(GameState::Splash)
     .add_plugins(DefaultPlugins)
+    .add_plugin(game_utils::TweenPlugin)
+    .add_plugin(game_particles::ParticlePlugin)
     .add_plugin(splash::SplashPlugin::<GameState>::create(
       GameState::Splash,
       GameState::Menu,
       4.0,
     ))
-    .add_plugin(game_audio::AudioPlugin)
+    .add_plugin(game_audio::AudioPlugin::<game_data::AudioLayer>::default())
     .add_plugin(main_menu::MainMenuPlugin::<GameState>::create(
       GameState::Menu,
       GameState::Game,
@@ -1,5 +1,5 @@
 use bevy::{app::AppExit, prelude::*};
-use game_utils::cleanup_system;
+use game_utils::{cleanup_system, Ease, Tween};
 use std::{fmt::Debug, hash::Hash};
 
 pub struct MainMenuPlugin<T> {
@@ -87,21 +87,30 @@ enum MenuButtonAction {
   Quit,
 }
 
-// This system handles changing all buttons color based on mouse interaction
+const BUTTON_FADE_SECONDS: f32 = 0.15;
+
+// This system fades the buttons color based on mouse interaction
 fn button_system(
-  mut interaction_query: Query<
-    (&Interaction, &mut UiColor, Option<&SelectedOption>),
+  mut commands: Commands,
+  interaction_query: Query<
+    (Entity, &Interaction, &UiColor, Option<&SelectedOption>),
     (Changed<Interaction>, With<Button>),
   >,
 ) {
-  for (interaction, mut color, selected) in interaction_query.iter_mut() {
-    *color = match (*interaction, selected) {
-      (Interaction::Clicked, _) => PRESSED_BUTTON.into(),
-      (Interaction::Hovered, Some(_)) => HOVERED_PRESSED_BUTTON.into(),
-      (Interaction::Hovered, None) => HOVERED_BUTTON.into(),
-      (Interaction::None, Some(_)) => PRESSED_BUTTON.into(),
-      (Interaction::None, None) => NORMAL_BUTTON.into(),
-    }
+  for (entity, interaction, color, selected) in interaction_query.iter() {
+    let target = match (*interaction, selected) {
+      (Interaction::Clicked, _) => PRESSED_BUTTON,
+      (Interaction::Hovered, Some(_)) => HOVERED_PRESSED_BUTTON,
+      (Interaction::Hovered, None) => HOVERED_BUTTON,
+      (Interaction::None, Some(_)) => PRESSED_BUTTON,
+      (Interaction::None, None) => NORMAL_BUTTON,
+    };
+    commands.entity(entity).insert(Tween::<UiColor>::new(
+      color.0,
+      target,
+      BUTTON_FADE_SECONDS,
+      Ease::QuadOut,
+    ));
   }
 }
 
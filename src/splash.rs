@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use game_utils::cleanup_system;
+use game_utils::{cleanup_system, Ease, Tween};
 use std::{fmt::Debug, hash::Hash};
 
 // Tag component used to tag entities added on the splash screen
@@ -8,6 +8,8 @@ struct OnSplashScreen;
 
 struct SplashTimer(Timer);
 
+const FADE_SECONDS: f32 = 0.75;
+
 pub struct SplashPlugin<T> {
   config: SplashScreenConfig<T>,
 }
@@ -67,11 +69,23 @@ where
       .spawn_bundle(Camera2dBundle::default())
       .insert(OnSplashScreen);
 
+    // fade the logo in and back out over the splash duration
+    let fade = (splash_config.duration / 4.).min(FADE_SECONDS);
+    let clear = Color::rgba(1., 1., 1., 0.);
     commands
       .spawn_bundle(SpriteBundle {
         texture: asset_server.load("splash.png"),
+        sprite: Sprite {
+          color: clear,
+          ..default()
+        },
         ..default()
       })
+      .insert(
+        Tween::<Sprite>::new(clear, Color::WHITE, fade, Ease::SineInOut)
+          .then(Color::WHITE, splash_config.duration - 2. * fade, Ease::Linear)
+          .then(clear, fade, Ease::SineInOut),
+      )
       .insert(OnSplashScreen);
 
     commands.insert_resource(SplashTimer(Timer::from_seconds(